@@ -0,0 +1,183 @@
+//!
+//! The actix module implements an [`actix_web`] extractor for [`QueryMap`].
+//! You need to enable the feature `actix` to access this extractor.
+//!
+
+use actix_web::{dev::Payload, http::StatusCode, Error, FromRequest, HttpRequest, ResponseError};
+use std::{
+    future::{ready, Ready},
+    sync::Arc,
+};
+
+use crate::{ParseError, ParseLimits, QueryMap};
+
+/// Default limit in bytes for the request's query string
+pub const DEFAULT_QUERY_LIMIT: usize = 16_384;
+
+type ErrorHandler = Arc<dyn Fn(ParseError, &HttpRequest) -> Error + Send + Sync>;
+
+/// Configuration for the [`QueryMap`] extractor.
+///
+/// Register it as app data to change the limits
+/// or the error returned when the query string is rejected.
+///
+/// ```
+/// use actix_web::{error, web, App, HttpResponse};
+/// use query_map::{actix::QueryMapConfig, ParseLimits, QueryMap};
+///
+/// let config = QueryMapConfig::default()
+///     .limit(4096)
+///     .parse_limits(ParseLimits {
+///         max_pairs: 32,
+///         ..ParseLimits::default()
+///     })
+///     .error_handler(|err, _req| {
+///         error::InternalError::from_response(err, HttpResponse::Conflict().finish()).into()
+///     });
+///
+/// let app = App::new()
+///     .app_data(config)
+///     .route("/", web::get().to(|query: QueryMap| async move {
+///         HttpResponse::Ok().body(query.first("foo").unwrap_or_default().to_string())
+///     }));
+/// ```
+#[derive(Clone)]
+pub struct QueryMapConfig {
+    limit: usize,
+    parse_limits: ParseLimits,
+    err_handler: Option<ErrorHandler>,
+}
+
+impl QueryMapConfig {
+    /// Set the maximum length in bytes of the query string
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Set the limits applied while parsing the query string
+    #[must_use]
+    pub fn parse_limits(mut self, limits: ParseLimits) -> Self {
+        self.parse_limits = limits;
+        self
+    }
+
+    /// Set a custom error handler for rejected query strings
+    #[must_use]
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(ParseError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.err_handler = Some(Arc::new(f));
+        self
+    }
+
+    fn parse(&self, query: &str) -> Result<QueryMap, ParseError> {
+        if query.len() > self.limit {
+            return Err(ParseError::TooLarge { limit: self.limit });
+        }
+        QueryMap::parse_with_limits(query, &self.parse_limits)
+    }
+}
+
+impl Default for QueryMapConfig {
+    fn default() -> Self {
+        QueryMapConfig {
+            limit: DEFAULT_QUERY_LIMIT,
+            parse_limits: ParseLimits::default(),
+            err_handler: None,
+        }
+    }
+}
+
+impl ResponseError for ParseError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+impl FromRequest for QueryMap {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let default_config = QueryMapConfig::default();
+        let config = req.app_data::<QueryMapConfig>().unwrap_or(&default_config);
+
+        ready(
+            config
+                .parse(req.query_string())
+                .map_err(|err| match &config.err_handler {
+                    Some(handler) => handler(err, req),
+                    None => err.into(),
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{error::InternalError, test::TestRequest, HttpResponse};
+
+    #[actix_rt::test]
+    async fn test_extract_query_map() {
+        let (req, mut pl) = TestRequest::with_uri("/?foo=bar&baz=quux&foo=qux").to_http_parts();
+
+        let map = QueryMap::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(vec!["bar", "qux"], map.all("foo").unwrap());
+        assert_eq!("quux", map.first("baz").unwrap());
+    }
+
+    #[actix_rt::test]
+    async fn test_extract_empty_query_map() {
+        let (req, mut pl) = TestRequest::with_uri("/").to_http_parts();
+
+        let map = QueryMap::from_request(&req, &mut pl).await.unwrap();
+        assert!(map.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_query_string_limits() {
+        let (req, mut pl) = TestRequest::with_uri("/?foo=bar&baz=quux")
+            .app_data(QueryMapConfig::default().limit(8))
+            .to_http_parts();
+
+        let err = QueryMap::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            err.as_response_error().status_code()
+        );
+
+        let (req, mut pl) = TestRequest::with_uri("/?foo=bar&baz=quux")
+            .app_data(QueryMapConfig::default().parse_limits(ParseLimits {
+                max_pairs: 1,
+                ..ParseLimits::default()
+            }))
+            .to_http_parts();
+
+        let err = QueryMap::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            "input contains more than 1 pairs",
+            err.as_response_error().to_string()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_custom_error_handler() {
+        let (req, mut pl) = TestRequest::with_uri("/?foo=bar")
+            .app_data(QueryMapConfig::default().limit(4).error_handler(|err, _| {
+                let resp = HttpResponse::UnprocessableEntity().finish();
+                InternalError::from_response(err, resp).into()
+            }))
+            .to_http_parts();
+
+        let err = QueryMap::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            err.as_response_error().error_response().status()
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::{error::Error, fmt};
+
+use crate::QueryMap;
+
+/// Error returned when a cookie can't be written into a header
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CookieError {
+    /// The name is not an RFC 6265 token
+    InvalidName {
+        /// The rejected name
+        name: String,
+    },
+    /// The value contains characters that are not cookie-octets, like `;` or spaces
+    InvalidValue {
+        /// Name of the cookie
+        name: String,
+        /// The rejected value
+        value: String,
+    },
+}
+
+impl fmt::Display for CookieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CookieError::InvalidName { name } => write!(f, "invalid cookie name `{}`", name),
+            CookieError::InvalidValue { name, value } => {
+                write!(f, "invalid value `{}` for cookie `{}`", value, name)
+            }
+        }
+    }
+}
+
+impl Error for CookieError {}
+
+impl QueryMap {
+    /// Parse the value of a `Cookie` request header into a [`QueryMap`].
+    ///
+    /// Cookies are split on `;` following RFC 6265. Values wrapped in double quotes
+    /// are unquoted, and no percent-decoding is applied.
+    /// Cookies with the same name, like path-scoped cookies, keep all their values.
+    ///
+    /// ```
+    /// use query_map::QueryMap;
+    ///
+    /// let map = QueryMap::from_cookie_header("session=abc; theme=\"dark\"; session=def");
+    /// assert_eq!(vec!["abc", "def"], map.all("session").unwrap());
+    /// assert_eq!("dark", map.first("theme").unwrap());
+    /// ```
+    #[must_use]
+    pub fn from_cookie_header(header: &str) -> QueryMap {
+        header.split(';').filter_map(parse_cookie_pair).collect()
+    }
+
+    /// Convert a [`QueryMap`] into the value of a `Cookie` request header.
+    ///
+    /// Values are written as they are, without percent-encoding.
+    /// Names must be RFC 6265 tokens and values cookie-octets, so a value
+    /// with a `;` can't inject another cookie.
+    ///
+    /// ```
+    /// use query_map::{CookieError, QueryMap};
+    ///
+    /// let map = QueryMap::from_cookie_header("session=abc");
+    /// assert_eq!(Ok("session=abc".to_string()), map.to_cookie_header());
+    ///
+    /// let map: QueryMap = vec![("session".to_string(), "x; admin=1".to_string())]
+    ///     .into_iter()
+    ///     .collect();
+    /// assert!(matches!(map.to_cookie_header(), Err(CookieError::InvalidValue { .. })));
+    /// ```
+    pub fn to_cookie_header(&self) -> Result<String, CookieError> {
+        Ok(self
+            .iter()
+            .map(|(name, value)| format_cookie_pair(name, value))
+            .collect::<Result<Vec<_>, _>>()?
+            .join("; "))
+    }
+}
+
+/// Write a single `name=value` cookie pair, rejecting invalid names and values
+pub(crate) fn format_cookie_pair(name: &str, value: &str) -> Result<String, CookieError> {
+    if name.is_empty() || !name.bytes().all(is_token_byte) {
+        return Err(CookieError::InvalidName {
+            name: name.to_string(),
+        });
+    }
+    if !value.bytes().all(is_cookie_octet) {
+        return Err(CookieError::InvalidValue {
+            name: name.to_string(),
+            value: value.to_string(),
+        });
+    }
+    Ok(format!("{}={}", name, value))
+}
+
+fn is_token_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+/// Visible ASCII, except `"`, `,`, `;` and `\`
+fn is_cookie_octet(b: u8) -> bool {
+    b.is_ascii_graphic() && !matches!(b, b'"' | b',' | b';' | b'\\')
+}
+
+/// Parse a single `name=value` cookie pair, ignoring pairs without a name
+pub(crate) fn parse_cookie_pair(pair: &str) -> Option<(String, String)> {
+    let (name, value) = pair.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    Some((name.to_string(), value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_cookie_header() {
+        let map =
+            QueryMap::from_cookie_header("a=1; b=\"two words\";c=%20; a=3; invalid; =nameless");
+
+        assert_eq!(vec!["1", "3"], map.all("a").unwrap());
+        assert_eq!("two words", map.first("b").unwrap());
+        assert_eq!("%20", map.first("c").unwrap());
+        assert_eq!(None, map.first("invalid"));
+        assert_eq!(3, map.keys().count());
+    }
+
+    #[test]
+    fn test_from_empty_cookie_header() {
+        assert!(QueryMap::from_cookie_header("").is_empty());
+        assert!(QueryMap::from_cookie_header(" ; ").is_empty());
+    }
+
+    #[test]
+    fn test_to_cookie_header() {
+        let map = QueryMap::from_cookie_header("a=1; a=2");
+        assert_eq!(Ok("a=1; a=2".to_string()), map.to_cookie_header());
+
+        let map = QueryMap::from_cookie_header("a=1; b=2; c=");
+        let header = map.to_cookie_header().unwrap();
+        assert_eq!(map, QueryMap::from_cookie_header(&header));
+    }
+
+    #[test]
+    fn test_to_cookie_header_rejects_invalid_cookies() {
+        let map: QueryMap = vec![("session".to_string(), "x; admin=1".to_string())]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            Err(CookieError::InvalidValue {
+                name: "session".into(),
+                value: "x; admin=1".into()
+            }),
+            map.to_cookie_header()
+        );
+
+        let map = QueryMap::from_cookie_header("b=\"two words\"");
+        assert!(matches!(
+            map.to_cookie_header(),
+            Err(CookieError::InvalidValue { .. })
+        ));
+
+        let map: QueryMap = vec![("a b".to_string(), "1".to_string())]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            Err(CookieError::InvalidName { name: "a b".into() }),
+            map.to_cookie_header()
+        );
+    }
+}
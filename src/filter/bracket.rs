@@ -0,0 +1,68 @@
+use crate::{
+    filter::{Condition, Filter, FilterConfig, FilterDialect, FilterError, KeyStyle, Operator},
+    QueryMap,
+};
+
+/// Dialect for `field[op]=value` and `field__op=value` params
+///
+/// Both key styles are read, [`BracketDialect::style`] is used to encode filters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BracketDialect {
+    /// Key style used to encode filters
+    pub style: KeyStyle,
+}
+
+impl FilterDialect for BracketDialect {
+    fn parse(&self, map: &QueryMap, config: &FilterConfig) -> Result<Filter, FilterError> {
+        let mut keys = map.keys().collect::<Vec<_>>();
+        keys.sort_unstable();
+
+        let mut conditions = Vec::new();
+        for key in keys {
+            let (field, op) = match split_key(key, config)? {
+                Some((field, op)) => (field, op),
+                None if config.is_known(key) => (key, Operator::Eq),
+                None => continue,
+            };
+            config.check(field, op)?;
+            for value in map.all(key).unwrap_or_default() {
+                conditions.push(Condition::new(field, op, value).with_kind(config.kind(field)));
+            }
+        }
+        Ok(Filter::new(conditions))
+    }
+
+    fn encode(&self, filter: &Filter) -> Result<QueryMap, FilterError> {
+        filter.encode(self.style)
+    }
+}
+
+/// Split `field[op]` and `field__op` keys for the fields declared in `config`.
+///
+/// Keys for other fields, like `page[number]`, are not filters and return `None`.
+/// Keys where the text after `__` is not an operator are plain field names,
+/// brackets after a declared field always contain an operator.
+fn split_key<'a>(
+    key: &'a str,
+    config: &FilterConfig,
+) -> Result<Option<(&'a str, Operator)>, FilterError> {
+    if let Some((field, rest)) = key.split_once('[') {
+        if let Some(op) = rest.strip_suffix(']') {
+            if !config.is_known(field) {
+                return Ok(None);
+            }
+            let op = op
+                .parse::<Operator>()
+                .map_err(|err| FilterError::UnknownOperator {
+                    key: key.to_string(),
+                    op: err.op,
+                })?;
+            return Ok(Some((field, op)));
+        }
+    }
+
+    Ok(key
+        .rsplit_once("__")
+        .and_then(|(field, op)| Some((field, op.parse().ok()?)))
+        .filter(|(field, _)| config.is_known(field)))
+}
@@ -0,0 +1,49 @@
+use crate::{
+    filter::{Condition, Filter, FilterConfig, FilterDialect, FilterError, Operator},
+    QueryMap,
+};
+
+/// Dialect for `field=op:value` params, like `price=gte:10`
+///
+/// Values without a known operator prefix are equality filters.
+/// Encoded values always include the operator, so values with colons are not misread.
+/// Lists are joined with commas, so list items with commas can't be encoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ColonDialect;
+
+impl FilterDialect for ColonDialect {
+    fn parse(&self, map: &QueryMap, config: &FilterConfig) -> Result<Filter, FilterError> {
+        let mut keys = map
+            .keys()
+            .filter(|key| config.is_known(key))
+            .collect::<Vec<_>>();
+        keys.sort_unstable();
+
+        let mut conditions = Vec::new();
+        for field in keys {
+            for value in map.all(field).unwrap_or_default() {
+                let (op, value) = value
+                    .split_once(':')
+                    .and_then(|(op, value)| Some((op.parse::<Operator>().ok()?, value)))
+                    .unwrap_or((Operator::Eq, value));
+                config.check(field, op)?;
+                conditions.push(Condition::new(field, op, value).with_kind(config.kind(field)));
+            }
+        }
+        Ok(Filter::new(conditions))
+    }
+
+    fn encode(&self, filter: &Filter) -> Result<QueryMap, FilterError> {
+        filter
+            .conditions()
+            .iter()
+            .map(|condition| {
+                let value = condition.value.to_param(&condition.field)?;
+                Ok((
+                    condition.field.clone(),
+                    format!("{}:{}", condition.op, value),
+                ))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,955 @@
+//!
+//! The filter module parses filter params like `price[gte]=10` or `price__gte=10`
+//! from a [`QueryMap`] into a [`Filter`].
+//!
+//! Only fields declared in a [`FilterConfig`] are read, keys for other fields,
+//! like `page[number]` or `fields[articles]`, are left alone, so filters can live
+//! next to other params.
+//! A key without operator, like `status=active`, is an equality filter.
+//!
+//! Other syntaxes are supported with a [`FilterDialect`]: [`BracketDialect`] for `field[op]=value`,
+//! [`ColonDialect`] for `field=op:value`, and [`ODataDialect`] for `filter=field eq 'value'`.
+//! All dialects share the same [`Filter`], so gateways can translate params between them.
+//! Fields declared with [`FilterConfig::typed_field`] keep the type of their values,
+//! so the OData dialect writes `price ge 10` instead of `price ge '10'`.
+//!
+//! ```
+//! use query_map::{filter::{FilterConfig, Operator}, QueryMap};
+//!
+//! let config = FilterConfig::new()
+//!     .field("price", &[Operator::Gte, Operator::Lt])
+//!     .field("status", &[Operator::Eq, Operator::In]);
+//!
+//! let map: QueryMap = vec![
+//!     ("price[gte]".to_string(), "10".to_string()),
+//!     ("status__in".to_string(), "a,b".to_string()),
+//!     ("page".to_string(), "2".to_string()),
+//! ]
+//! .into_iter()
+//! .collect();
+//!
+//! let filter = config.parse(&map).unwrap();
+//! assert_eq!(2, filter.conditions().len());
+//! ```
+//!
+
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::QueryMap;
+
+mod bracket;
+pub use bracket::BracketDialect;
+
+mod colon;
+pub use colon::ColonDialect;
+
+mod odata;
+pub use odata::ODataDialect;
+
+/// Comparison applied by a filter condition
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operator {
+    /// Equal to
+    Eq,
+    /// Not equal to
+    Ne,
+    /// Greater than
+    Gt,
+    /// Greater than or equal to
+    Gte,
+    /// Less than
+    Lt,
+    /// Less than or equal to
+    Lte,
+    /// Equal to one of the values
+    In,
+    /// Not equal to any of the values
+    Nin,
+    /// Contains the value
+    Contains,
+    /// Starts with the value
+    StartsWith,
+    /// Ends with the value
+    EndsWith,
+}
+
+impl Operator {
+    /// Every operator
+    pub const ALL: &'static [Operator] = &[
+        Operator::Eq,
+        Operator::Ne,
+        Operator::Gt,
+        Operator::Gte,
+        Operator::Lt,
+        Operator::Lte,
+        Operator::In,
+        Operator::Nin,
+        Operator::Contains,
+        Operator::StartsWith,
+        Operator::EndsWith,
+    ];
+
+    /// Return the name of the operator in params
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operator::Eq => "eq",
+            Operator::Ne => "ne",
+            Operator::Gt => "gt",
+            Operator::Gte => "gte",
+            Operator::Lt => "lt",
+            Operator::Lte => "lte",
+            Operator::In => "in",
+            Operator::Nin => "nin",
+            Operator::Contains => "contains",
+            Operator::StartsWith => "startswith",
+            Operator::EndsWith => "endswith",
+        }
+    }
+
+    /// Return true if the operator takes a list of values
+    #[must_use]
+    pub fn takes_list(&self) -> bool {
+        matches!(self, Operator::In | Operator::Nin)
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Operator {
+    type Err = ParseOperatorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Operator::ALL
+            .iter()
+            .find(|op| op.as_str().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| ParseOperatorError { op: s.to_string() })
+    }
+}
+
+/// Error returned when a string is not the name of an [`Operator`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseOperatorError {
+    /// The rejected operator
+    pub op: String,
+}
+
+impl fmt::Display for ParseOperatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown operator `{}`", self.op)
+    }
+}
+
+impl Error for ParseOperatorError {}
+
+/// Value of a filter condition
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FilterValue {
+    /// A single value
+    Single(String),
+    /// A list of values, used by [`Operator::In`] and [`Operator::Nin`]
+    List(Vec<String>),
+}
+
+impl FilterValue {
+    /// Write the value in a single param, joining lists with commas.
+    ///
+    /// List items with commas are rejected, because they could not be split back.
+    pub(crate) fn to_param(&self, field: &str) -> Result<String, FilterError> {
+        match self {
+            FilterValue::Single(value) => Ok(value.clone()),
+            FilterValue::List(values) => match values.iter().find(|v| v.contains(',')) {
+                Some(value) => Err(FilterError::UnencodableValue {
+                    field: field.to_string(),
+                    value: value.clone(),
+                }),
+                None => Ok(values.join(",")),
+            },
+        }
+    }
+}
+
+impl fmt::Display for FilterValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterValue::Single(value) => f.write_str(value),
+            FilterValue::List(values) => f.write_str(&values.join(",")),
+        }
+    }
+}
+
+/// Type of the values of a condition
+///
+/// Params are untyped, so the kind of a field is declared with [`FilterConfig::typed_field`].
+/// Dialects with typed literals, like [`ODataDialect`], use it to write values,
+/// so `price ge 10` compares a number instead of the string `'10'`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ValueKind {
+    /// A string, the kind of undeclared fields
+    #[default]
+    String,
+    /// An integer or decimal number
+    Number,
+    /// `true` or `false`
+    Boolean,
+    /// A date, like `2020-01-31`
+    Date,
+    /// A date and time with an offset, like `2020-01-31T12:00:00Z`
+    DateTimeOffset,
+    /// A GUID, like `01234567-89ab-cdef-0123-456789abcdef`
+    Guid,
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ValueKind::String => "string",
+            ValueKind::Number => "number",
+            ValueKind::Boolean => "boolean",
+            ValueKind::Date => "date",
+            ValueKind::DateTimeOffset => "date time offset",
+            ValueKind::Guid => "GUID",
+        })
+    }
+}
+
+/// A single comparison between a field and a value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Condition {
+    /// Field name
+    pub field: String,
+    /// Comparison operator
+    pub op: Operator,
+    /// Value to compare against
+    pub value: FilterValue,
+    /// Type of the value, every item of a list has the same type
+    pub kind: ValueKind,
+}
+
+impl Condition {
+    /// Create a string condition, splitting the value on commas for list operators
+    #[must_use]
+    pub fn new(field: impl Into<String>, op: Operator, value: &str) -> Self {
+        let value = if op.takes_list() {
+            FilterValue::List(value.split(',').map(String::from).collect())
+        } else {
+            FilterValue::Single(value.to_string())
+        };
+        Condition {
+            field: field.into(),
+            op,
+            value,
+            kind: ValueKind::String,
+        }
+    }
+
+    /// Set the type of the value
+    #[must_use]
+    pub fn with_kind(mut self, kind: ValueKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+/// Conditions that must all match
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Filter(Vec<Condition>);
+
+impl Filter {
+    /// Create a filter from a list of conditions
+    #[must_use]
+    pub fn new(conditions: Vec<Condition>) -> Self {
+        Filter(conditions)
+    }
+
+    /// Return the conditions in this filter
+    #[must_use]
+    pub fn conditions(&self) -> &[Condition] {
+        &self.0
+    }
+
+    /// Return true if there are no conditions
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Encode the filter into query params with the given key style.
+    ///
+    /// Equality conditions are written without operator.
+    /// Lists are joined with commas, so list items with commas are rejected.
+    pub fn encode(&self, style: KeyStyle) -> Result<QueryMap, FilterError> {
+        self.0
+            .iter()
+            .map(|condition| {
+                let key = match condition.op {
+                    Operator::Eq => condition.field.clone(),
+                    op => style.key(&condition.field, op),
+                };
+                Ok((key, condition.value.to_param(&condition.field)?))
+            })
+            .collect()
+    }
+}
+
+/// How the operator is attached to the field name in a key
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyStyle {
+    /// `field[op]`
+    #[default]
+    Bracket,
+    /// `field__op`
+    DoubleUnderscore,
+}
+
+impl KeyStyle {
+    fn key(&self, field: &str, op: Operator) -> String {
+        match self {
+            KeyStyle::Bracket => format!("{}[{}]", field, op),
+            KeyStyle::DoubleUnderscore => format!("{}__{}", field, op),
+        }
+    }
+}
+
+/// Error returned when filter params are not valid
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FilterError {
+    /// The field is not declared in the [`FilterConfig`]
+    UnknownField {
+        /// The rejected field
+        field: String,
+    },
+    /// The operator is not known
+    UnknownOperator {
+        /// The key with the operator
+        key: String,
+        /// The rejected operator
+        op: String,
+    },
+    /// The operator is not allowed for the field
+    OperatorNotAllowed {
+        /// Field name
+        field: String,
+        /// The rejected operator
+        op: Operator,
+    },
+    /// The filter expression is not valid
+    Syntax {
+        /// Byte offset of the error in the expression
+        position: usize,
+        /// Description of the error
+        message: String,
+    },
+    /// The filter expression is valid, but it can't be represented as a [`Filter`],
+    /// like an `or` expression
+    Unsupported {
+        /// Description of the error
+        message: String,
+    },
+    /// A list item contains a comma, so it can't be written in a dialect
+    /// that joins lists with commas
+    UnencodableValue {
+        /// Field name
+        field: String,
+        /// The rejected list item
+        value: String,
+    },
+    /// A value is not a valid literal of the field's [`ValueKind`],
+    /// so it can't be written in a dialect with typed literals
+    InvalidValue {
+        /// Field name
+        field: String,
+        /// The rejected value
+        value: String,
+        /// The expected type
+        kind: ValueKind,
+    },
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::UnknownField { field } => write!(f, "unknown filter field `{}`", field),
+            FilterError::UnknownOperator { key, op } => {
+                write!(f, "`{}` uses an unknown operator `{}`", key, op)
+            }
+            FilterError::OperatorNotAllowed { field, op } => {
+                write!(f, "operator `{}` is not allowed for field `{}`", op, field)
+            }
+            FilterError::Syntax { position, message } => {
+                write!(f, "invalid filter at position {}: {}", position, message)
+            }
+            FilterError::Unsupported { message } => write!(f, "unsupported filter: {}", message),
+            FilterError::UnencodableValue { field, value } => write!(
+                f,
+                "value `{}` for field `{}` contains a comma and can't be joined in a list",
+                value, field
+            ),
+            FilterError::InvalidValue { field, value, kind } => write!(
+                f,
+                "value `{}` for field `{}` is not a valid {}",
+                value, field, kind
+            ),
+        }
+    }
+}
+
+impl Error for FilterError {}
+
+/// A syntax to write a [`Filter`] as query params
+///
+/// Dialects convert to and from the same [`Filter`], so params can be translated
+/// from one dialect to another.
+pub trait FilterDialect {
+    /// Read the conditions for the fields accepted by `config` from a [`QueryMap`]
+    fn parse(&self, map: &QueryMap, config: &FilterConfig) -> Result<Filter, FilterError>;
+
+    /// Encode a [`Filter`] into query params, rejecting values the dialect can't represent
+    fn encode(&self, filter: &Filter) -> Result<QueryMap, FilterError>;
+}
+
+/// Fields and operators accepted in filter params
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FilterConfig {
+    fields: Vec<(String, Vec<Operator>, ValueKind)>,
+}
+
+impl FilterConfig {
+    /// Create a config that doesn't accept any field
+    #[must_use]
+    pub fn new() -> Self {
+        FilterConfig::default()
+    }
+
+    /// Accept a string field with the given operators
+    #[must_use]
+    pub fn field(self, name: impl Into<String>, ops: &[Operator]) -> Self {
+        self.typed_field(name, ops, ValueKind::String)
+    }
+
+    /// Accept a field with the given operators and type of values
+    #[must_use]
+    pub fn typed_field(
+        mut self,
+        name: impl Into<String>,
+        ops: &[Operator],
+        kind: ValueKind,
+    ) -> Self {
+        self.fields.push((name.into(), ops.to_vec(), kind));
+        self
+    }
+
+    /// Read `field[op]` and `field__op` params from a [`QueryMap`]
+    pub fn parse(&self, map: &QueryMap) -> Result<Filter, FilterError> {
+        self.parse_with(&BracketDialect::default(), map)
+    }
+
+    /// Read filter params written in the given dialect from a [`QueryMap`]
+    pub fn parse_with<D>(&self, dialect: &D, map: &QueryMap) -> Result<Filter, FilterError>
+    where
+        D: FilterDialect + ?Sized,
+    {
+        dialect.parse(map, self)
+    }
+
+    /// Reject conditions with fields or operators that are not accepted
+    pub fn validate(&self, filter: &Filter) -> Result<(), FilterError> {
+        filter
+            .conditions()
+            .iter()
+            .try_for_each(|condition| self.check(&condition.field, condition.op))
+    }
+
+    pub(crate) fn is_known(&self, field: &str) -> bool {
+        self.fields.iter().any(|(name, _, _)| name == field)
+    }
+
+    /// Return the declared type of a field, strings for undeclared fields
+    pub(crate) fn kind(&self, field: &str) -> ValueKind {
+        self.fields
+            .iter()
+            .find(|(name, _, _)| name == field)
+            .map_or(ValueKind::String, |(_, _, kind)| *kind)
+    }
+
+    pub(crate) fn check(&self, field: &str, op: Operator) -> Result<(), FilterError> {
+        let ops = self
+            .fields
+            .iter()
+            .find(|(name, _, _)| name == field)
+            .map(|(_, ops, _)| ops)
+            .ok_or_else(|| FilterError::UnknownField {
+                field: field.to_string(),
+            })?;
+        if ops.contains(&op) {
+            Ok(())
+        } else {
+            Err(FilterError::OperatorNotAllowed {
+                field: field.to_string(),
+                op,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_map as map;
+
+    fn config() -> FilterConfig {
+        FilterConfig::new()
+            .field("price", &[Operator::Gte, Operator::Lt])
+            .field("status", &[Operator::Eq, Operator::In])
+            .field("created__at", Operator::ALL)
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = map(&[
+            ("price[gte]", "10"),
+            ("price__lt", "100"),
+            ("status[in]", "a,b"),
+            ("created__at", "today"),
+            ("page", "2"),
+        ]);
+
+        let filter = config().parse(&data).unwrap();
+        assert_eq!(
+            vec![
+                Condition {
+                    field: "created__at".into(),
+                    op: Operator::Eq,
+                    value: FilterValue::Single("today".into()),
+                    kind: ValueKind::String
+                },
+                Condition {
+                    field: "price".into(),
+                    op: Operator::Gte,
+                    value: FilterValue::Single("10".into()),
+                    kind: ValueKind::String
+                },
+                Condition {
+                    field: "price".into(),
+                    op: Operator::Lt,
+                    value: FilterValue::Single("100".into()),
+                    kind: ValueKind::String
+                },
+                Condition {
+                    field: "status".into(),
+                    op: Operator::In,
+                    value: FilterValue::List(vec!["a".into(), "b".into()]),
+                    kind: ValueKind::String
+                },
+            ],
+            filter.conditions()
+        );
+    }
+
+    #[test]
+    fn test_parse_operator() {
+        assert_eq!(Ok(Operator::Gte), "gte".parse());
+        assert_eq!(Ok(Operator::StartsWith), "StartsWith".parse());
+
+        let err = "between".parse::<Operator>().unwrap_err();
+        assert_eq!(
+            ParseOperatorError {
+                op: "between".into()
+            },
+            err
+        );
+        assert_eq!("unknown operator `between`", err.to_string());
+    }
+
+    #[test]
+    fn test_allowlists() {
+        assert_eq!(
+            Ok(Filter::default()),
+            config().parse(&map(&[("secret[eq]", "1"), ("secret__eq", "1")]))
+        );
+        assert_eq!(
+            Err(FilterError::UnknownField {
+                field: "secret".into()
+            }),
+            config().validate(&Filter::new(vec![Condition::new(
+                "secret",
+                Operator::Eq,
+                "1"
+            )]))
+        );
+        assert_eq!(
+            Err(FilterError::OperatorNotAllowed {
+                field: "price".into(),
+                op: Operator::Eq
+            }),
+            config().parse(&map(&[("price", "1")]))
+        );
+        assert_eq!(
+            Err(FilterError::UnknownOperator {
+                key: "price[between]".into(),
+                op: "between".into()
+            }),
+            config().parse(&map(&[("price[between]", "1")]))
+        );
+    }
+
+    #[test]
+    fn test_parse_next_to_jsonapi_params() {
+        let data = map(&[
+            ("price[gte]", "10"),
+            ("page[number]", "2"),
+            ("page[size]", "20"),
+            ("fields[articles]", "title,body"),
+            ("sort", "-created"),
+        ]);
+
+        let filter = config().parse(&data).unwrap();
+        assert_eq!(
+            &[Condition::new("price", Operator::Gte, "10")],
+            filter.conditions()
+        );
+        let fieldsets = crate::jsonapi::Fieldsets::parse(&data).unwrap();
+        assert_eq!(
+            Some(&["title".to_string(), "body".to_string()][..]),
+            fieldsets.fields("articles")
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let filter = Filter::new(vec![
+            Condition::new("price", Operator::Gte, "10"),
+            Condition::new("status", Operator::In, "a,b"),
+            Condition::new("name", Operator::Eq, "x"),
+        ]);
+
+        let bracket = filter.encode(KeyStyle::Bracket).unwrap();
+        assert_eq!("10", bracket.first("price[gte]").unwrap());
+        assert_eq!("a,b", bracket.first("status[in]").unwrap());
+        assert_eq!("x", bracket.first("name").unwrap());
+
+        let underscore = filter.encode(KeyStyle::DoubleUnderscore).unwrap();
+        assert_eq!("10", underscore.first("price__gte").unwrap());
+
+        let config = FilterConfig::new()
+            .field("price", &[Operator::Gte])
+            .field("status", &[Operator::In])
+            .field("name", &[Operator::Eq]);
+        assert_eq!(Ok(()), config.validate(&filter));
+        let mut reparsed = config.parse(&underscore).unwrap().conditions().to_vec();
+        let mut expected = filter.conditions().to_vec();
+        reparsed.sort_by(|a, b| a.field.cmp(&b.field));
+        expected.sort_by(|a, b| a.field.cmp(&b.field));
+        assert_eq!(expected, reparsed);
+    }
+
+    #[test]
+    fn test_colon_dialect() {
+        let data = map(&[
+            ("price", "gte:10"),
+            ("price", "lt:100"),
+            ("status", "in:a,b"),
+            ("created__at", "12:00"),
+        ]);
+
+        let filter = config().parse_with(&ColonDialect, &data).unwrap();
+        assert_eq!(4, filter.conditions().len());
+        assert_eq!(
+            &Condition::new("created__at", Operator::Eq, "12:00"),
+            &filter.conditions()[0]
+        );
+        assert_eq!(
+            &Condition::new("status", Operator::In, "a,b"),
+            &filter.conditions()[3]
+        );
+
+        let encoded = ColonDialect.encode(&filter).unwrap();
+        assert_eq!("eq:12:00", encoded.first("created__at").unwrap());
+        assert_eq!(Ok(filter), config().parse_with(&ColonDialect, &encoded));
+    }
+
+    #[test]
+    fn test_odata_dialect() {
+        let data = map(&[(
+            "filter",
+            "price ge 10 and status in ('a', 'it''s') and not (created__at in (1,2)) and startswith(created__at,'20')",
+        )]);
+
+        let filter = config()
+            .parse_with(&ODataDialect::default(), &data)
+            .unwrap();
+        assert_eq!(
+            vec![
+                Condition::new("price", Operator::Gte, "10").with_kind(ValueKind::Number),
+                Condition {
+                    field: "status".into(),
+                    op: Operator::In,
+                    value: FilterValue::List(vec!["a".into(), "it's".into()]),
+                    kind: ValueKind::String
+                },
+                Condition::new("created__at", Operator::Nin, "1,2").with_kind(ValueKind::Number),
+                Condition::new("created__at", Operator::StartsWith, "20"),
+            ],
+            filter.conditions()
+        );
+
+        // keywords are case-insensitive, like in `$filter`
+        assert_eq!(
+            Ok(filter.clone()),
+            config().parse_with(
+                &ODataDialect::default(),
+                &map(&[(
+                    "filter",
+                    "price GE 10 And status IN ('a', 'it''s') And NOT (created__at in (1,2)) And StartsWith(created__at,'20')",
+                )])
+            )
+        );
+
+        let encoded = ODataDialect::default().encode(&filter).unwrap();
+        assert_eq!(
+            "price ge 10 and status in ('a','it''s') and not (created__at in (1,2)) and startswith(created__at,'20')",
+            encoded.first("filter").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_odata_errors() {
+        let parse = |expression| {
+            config().parse_with(&ODataDialect::default(), &map(&[("filter", expression)]))
+        };
+
+        assert_eq!(
+            Err(FilterError::Unsupported {
+                message: "`or` is not supported".into()
+            }),
+            parse("price ge 10 or price lt 5")
+        );
+        assert_eq!(
+            Err(FilterError::Unsupported {
+                message: "`null` is not supported".into()
+            }),
+            parse("status eq null")
+        );
+        assert_eq!(
+            Err(FilterError::Unsupported {
+                message: "expected a field name".into()
+            }),
+            parse("length(status) eq 1")
+        );
+        assert_eq!(
+            Err(FilterError::Unsupported {
+                message: "`tolower` is not supported".into()
+            }),
+            parse("tolower(status)")
+        );
+        assert_eq!(
+            Err(FilterError::Syntax {
+                position: 6,
+                message: "expected an operator or the end of the expression".into()
+            }),
+            parse("price between 1")
+        );
+        assert_eq!(
+            Err(FilterError::Syntax {
+                position: 10,
+                message: "unterminated string".into()
+            }),
+            parse("status eq 'a")
+        );
+        assert_eq!(
+            Err(FilterError::OperatorNotAllowed {
+                field: "price".into(),
+                op: Operator::Eq
+            }),
+            parse("price eq 1")
+        );
+    }
+
+    #[test]
+    fn test_odata_long_conjunction() {
+        let expression = vec!["price ge 1"; 5_000].join(" and ");
+        let filter = config()
+            .parse_with(&ODataDialect::default(), &map(&[("filter", &expression)]))
+            .unwrap();
+        assert_eq!(5_000, filter.conditions().len());
+    }
+
+    #[test]
+    fn test_translate_dialects() {
+        let data = map(&[("price", "gte:10"), ("status", "in:a,b")]);
+        let filter = config().parse_with(&ColonDialect, &data).unwrap();
+
+        let bracket = BracketDialect::default().encode(&filter).unwrap();
+        assert_eq!("10", bracket.first("price[gte]").unwrap());
+        assert_eq!(Ok(filter.clone()), config().parse(&bracket));
+
+        let odata = ODataDialect::default().encode(&filter).unwrap();
+        assert_eq!(
+            "price ge '10' and status in ('a','b')",
+            odata.first("filter").unwrap()
+        );
+        assert_eq!(
+            Ok(filter),
+            config().parse_with(&ODataDialect::default(), &odata)
+        );
+    }
+
+    #[test]
+    fn test_round_trip_all_dialects() {
+        let dialects: [&dyn FilterDialect; 3] = [
+            &ColonDialect,
+            &BracketDialect::default(),
+            &ODataDialect::default(),
+        ];
+        let filter = Filter::new(vec![
+            Condition::new("created__at", Operator::Contains, "a,b"),
+            Condition::new("price", Operator::Gte, "10"),
+            Condition::new("status", Operator::In, "a,b"),
+        ]);
+
+        let mut current = filter.clone();
+        for dialect in dialects.iter().chain(dialects.iter()) {
+            let encoded = dialect.encode(&current).unwrap();
+            current = config().parse_with(*dialect, &encoded).unwrap();
+            assert_eq!(filter, current);
+        }
+    }
+
+    #[test]
+    fn test_typed_fields_round_trip() {
+        use crate::odata::{parse_filter, BinaryOp, Expr, Literal};
+
+        // collect the values compared in an `and` chain, like a backend would
+        fn values(expr: Expr, out: &mut Vec<Expr>) {
+            match expr {
+                Expr::Binary {
+                    op: BinaryOp::And,
+                    left,
+                    right,
+                } => {
+                    values(*left, out);
+                    values(*right, out);
+                }
+                Expr::Binary { right, .. } => out.push(*right),
+                Expr::In { list, .. } => out.extend(list),
+                expr => out.push(expr),
+            }
+        }
+
+        let config = FilterConfig::new()
+            .typed_field("price", &[Operator::Gte, Operator::Lt], ValueKind::Number)
+            .typed_field("active", &[Operator::Eq], ValueKind::Boolean)
+            .typed_field("created", &[Operator::Gte], ValueKind::Date)
+            .field("status", &[Operator::In]);
+        let data = map(&[
+            ("price[gte]", "10"),
+            ("price__lt", "99.5"),
+            ("active", "true"),
+            ("created[gte]", "2020-01-31"),
+            ("status[in]", "10,b"),
+        ]);
+        let filter = config.parse(&data).unwrap();
+        assert_eq!(ValueKind::Number, filter.conditions()[2].kind);
+
+        let odata = ODataDialect::default().encode(&filter).unwrap();
+        let expression = odata.first("filter").unwrap();
+        assert_eq!(
+            "active eq true and created ge 2020-01-31 and price ge 10 and price lt 99.5 and status in ('10','b')",
+            expression
+        );
+
+        let mut literals = Vec::new();
+        values(parse_filter(expression).unwrap(), &mut literals);
+        assert_eq!(
+            vec![
+                Expr::Literal(Literal::Bool(true)),
+                Expr::Literal(Literal::Date("2020-01-31".into())),
+                Expr::Literal(Literal::Int(10)),
+                Expr::Literal(Literal::Float(99.5)),
+                Expr::Literal(Literal::String("10".into())),
+                Expr::Literal(Literal::String("b".into())),
+            ],
+            literals
+        );
+
+        assert_eq!(
+            Ok(filter.clone()),
+            config.parse_with(&ODataDialect::default(), &odata)
+        );
+        let bracket = BracketDialect::default().encode(&filter).unwrap();
+        assert_eq!(Ok(filter), config.parse(&bracket));
+    }
+
+    #[test]
+    fn test_reject_invalid_typed_values() {
+        let config = FilterConfig::new().typed_field("price", &[Operator::Gte], ValueKind::Number);
+        let filter = config.parse(&map(&[("price[gte]", "1 or true")])).unwrap();
+
+        let err = ODataDialect::default().encode(&filter).unwrap_err();
+        assert_eq!(
+            FilterError::InvalidValue {
+                field: "price".into(),
+                value: "1 or true".into(),
+                kind: ValueKind::Number
+            },
+            err
+        );
+        assert_eq!(
+            "value `1 or true` for field `price` is not a valid number",
+            err.to_string()
+        );
+
+        assert!(ODataDialect::default()
+            .encode(&Filter::new(vec![Condition::new(
+                "price",
+                Operator::Gte,
+                "true"
+            )
+            .with_kind(ValueKind::Number)]))
+            .is_err());
+        assert_eq!(
+            Err(FilterError::Unsupported {
+                message: "list items must have the same type".into()
+            }),
+            config.parse_with(
+                &ODataDialect::default(),
+                &map(&[("filter", "price in (1,'a')")])
+            )
+        );
+    }
+
+    #[test]
+    fn test_reject_lists_with_commas() {
+        let filter = config()
+            .parse_with(
+                &ODataDialect::default(),
+                &map(&[("filter", "status in ('a,b','c')")]),
+            )
+            .unwrap();
+        assert_eq!(
+            &FilterValue::List(vec!["a,b".into(), "c".into()]),
+            &filter.conditions()[0].value
+        );
+
+        let err = FilterError::UnencodableValue {
+            field: "status".into(),
+            value: "a,b".into(),
+        };
+        assert_eq!(Err(err.clone()), ColonDialect.encode(&filter));
+        assert_eq!(Err(err), BracketDialect::default().encode(&filter));
+
+        let odata = ODataDialect::default().encode(&filter).unwrap();
+        assert_eq!("status in ('a,b','c')", odata.first("filter").unwrap());
+        assert_eq!(
+            Ok(filter),
+            config().parse_with(&ODataDialect::default(), &odata)
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{
+    filter::{
+        Condition, Filter, FilterConfig, FilterDialect, FilterError, FilterValue, Operator,
+        ValueKind,
+    },
+    odata::{parse_filter, BinaryOp, Expr, Literal, ODataError, UnaryOp},
+    QueryMap,
+};
+
+/// Default name of the param with OData style filters
+pub const DEFAULT_ODATA_PARAM: &str = "filter";
+
+/// Dialect for OData style expressions, like `filter=price ge 10 and status in ('a','b')`
+///
+/// Expressions are parsed with [`parse_filter`], and must be conjunctions of comparisons
+/// (`eq`, `ne`, `gt`, `ge`, `lt`, `le`) between a field and a literal, `in` lists,
+/// `not (field in (...))`, and the `contains`, `startswith` and `endswith` functions.
+/// Other expressions, like `or` or `null` comparisons, are rejected with [`FilterError::Unsupported`].
+/// Values are written as literals of their condition's [`ValueKind`], so numbers, booleans,
+/// dates and GUIDs are not quoted, and parsed conditions take their kind from the literals.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ODataDialect {
+    /// Name of the param with the expression
+    pub param: String,
+}
+
+impl Default for ODataDialect {
+    fn default() -> Self {
+        ODataDialect {
+            param: DEFAULT_ODATA_PARAM.to_string(),
+        }
+    }
+}
+
+impl FilterDialect for ODataDialect {
+    fn parse(&self, map: &QueryMap, config: &FilterConfig) -> Result<Filter, FilterError> {
+        let mut conditions = Vec::new();
+        for expression in map.all(&self.param).unwrap_or_default() {
+            let expr = parse_filter(expression).map_err(|err| match err {
+                ODataError::Syntax {
+                    position, message, ..
+                } => FilterError::Syntax { position, message },
+                err => unsupported(&err.to_string()),
+            })?;
+            lower(expr, &mut conditions)?;
+        }
+        for condition in &conditions {
+            config.check(&condition.field, condition.op)?;
+        }
+        Ok(Filter::new(conditions))
+    }
+
+    fn encode(&self, filter: &Filter) -> Result<QueryMap, FilterError> {
+        if filter.is_empty() {
+            return Ok(QueryMap::default());
+        }
+        let expression = filter
+            .conditions()
+            .iter()
+            .map(encode_condition)
+            .collect::<Result<Vec<_>, _>>()?
+            .join(" and ");
+        Ok(std::iter::once((self.param.clone(), expression)).collect())
+    }
+}
+
+fn encode_condition(condition: &Condition) -> Result<String, FilterError> {
+    let field = &condition.field;
+    let literal = |value: &String| {
+        literal(value, condition.kind).ok_or_else(|| FilterError::InvalidValue {
+            field: field.clone(),
+            value: value.clone(),
+            kind: condition.kind,
+        })
+    };
+    let value = match &condition.value {
+        FilterValue::Single(value) => literal(value)?,
+        FilterValue::List(values) => format!(
+            "({})",
+            values
+                .iter()
+                .map(literal)
+                .collect::<Result<Vec<_>, _>>()?
+                .join(",")
+        ),
+    };
+    Ok(match condition.op {
+        Operator::Nin => format!("not ({} in {})", field, value),
+        Operator::Contains | Operator::StartsWith | Operator::EndsWith => {
+            format!("{}({},{})", condition.op, field, value)
+        }
+        op => format!("{} {} {}", field, odata_operator(op), value),
+    })
+}
+
+fn odata_operator(op: Operator) -> &'static str {
+    match op {
+        Operator::Gte => "ge",
+        Operator::Lte => "le",
+        op => op.as_str(),
+    }
+}
+
+/// Write a literal of the given kind.
+///
+/// Strings are quoted, other values are written as they are,
+/// after checking they parse back into a single literal of the same kind,
+/// so a value like `1 or true` can't change the expression.
+fn literal(value: &str, kind: ValueKind) -> Option<String> {
+    match kind {
+        ValueKind::String => Some(format!("'{}'", value.replace('\'', "''"))),
+        kind => match parse_filter(value) {
+            Ok(Expr::Literal(literal)) if is_kind(&literal, kind) => Some(value.to_string()),
+            _ => None,
+        },
+    }
+}
+
+fn is_kind(literal: &Literal, kind: ValueKind) -> bool {
+    matches!(
+        (literal, kind),
+        (Literal::Bool(_), ValueKind::Boolean)
+            | (Literal::Int(_) | Literal::Float(_), ValueKind::Number)
+            | (Literal::Date(_), ValueKind::Date)
+            | (Literal::DateTimeOffset(_), ValueKind::DateTimeOffset)
+            | (Literal::Guid(_), ValueKind::Guid)
+    )
+}
+
+/// Convert a conjunction of expressions into conditions, in the order they are written.
+///
+/// `and` chains are walked with a stack, since long chains build deep trees.
+fn lower(expr: Expr, conditions: &mut Vec<Condition>) -> Result<(), FilterError> {
+    let mut pending = vec![expr];
+    while let Some(expr) = pending.pop() {
+        match expr {
+            Expr::Binary {
+                op: BinaryOp::And,
+                left,
+                right,
+            } => {
+                pending.push(*right);
+                pending.push(*left);
+            }
+            expr => conditions.push(condition(expr)?),
+        }
+    }
+    Ok(())
+}
+
+fn condition(expr: Expr) -> Result<Condition, FilterError> {
+    match expr {
+        Expr::Binary {
+            op: BinaryOp::Or, ..
+        } => Err(unsupported("`or` is not supported")),
+        Expr::Binary { op, left, right } => {
+            let op = comparison(op).ok_or_else(unsupported_expression)?;
+            let (value, kind) = value(*right)?;
+            Ok(Condition {
+                field: field(*left)?,
+                op,
+                value: FilterValue::Single(value),
+                kind,
+            })
+        }
+        Expr::In { expr, list } => list_condition(*expr, Operator::In, list),
+        Expr::Unary {
+            op: UnaryOp::Not,
+            expr,
+        } => match *expr {
+            Expr::In { expr, list } => list_condition(*expr, Operator::Nin, list),
+            _ => Err(unsupported_expression()),
+        },
+        Expr::Call { function, args } => {
+            let op = function
+                .parse::<Operator>()
+                .ok()
+                .filter(|op| {
+                    matches!(
+                        op,
+                        Operator::Contains | Operator::StartsWith | Operator::EndsWith
+                    )
+                })
+                .ok_or_else(|| unsupported(&format!("`{}` is not supported", function)))?;
+            let mut args = args.into_iter();
+            match (args.next(), args.next()) {
+                (Some(field_arg), Some(value_arg)) => {
+                    let (value, kind) = value(value_arg)?;
+                    Ok(Condition {
+                        field: field(field_arg)?,
+                        op,
+                        value: FilterValue::Single(value),
+                        kind,
+                    })
+                }
+                _ => Err(unsupported_expression()),
+            }
+        }
+        _ => Err(unsupported_expression()),
+    }
+}
+
+fn list_condition(expr: Expr, op: Operator, list: Vec<Expr>) -> Result<Condition, FilterError> {
+    let field = field(expr)?;
+    let mut values = Vec::with_capacity(list.len());
+    let mut list_kind = None;
+    for item in list {
+        let (value, kind) = value(item)?;
+        if list_kind.get_or_insert(kind) != &kind {
+            return Err(unsupported("list items must have the same type"));
+        }
+        values.push(value);
+    }
+    Ok(Condition {
+        field,
+        op,
+        value: FilterValue::List(values),
+        kind: list_kind.unwrap_or_default(),
+    })
+}
+
+fn comparison(op: BinaryOp) -> Option<Operator> {
+    match op {
+        BinaryOp::Eq => Some(Operator::Eq),
+        BinaryOp::Ne => Some(Operator::Ne),
+        BinaryOp::Gt => Some(Operator::Gt),
+        BinaryOp::Ge => Some(Operator::Gte),
+        BinaryOp::Lt => Some(Operator::Lt),
+        BinaryOp::Le => Some(Operator::Lte),
+        _ => None,
+    }
+}
+
+fn field(expr: Expr) -> Result<String, FilterError> {
+    match expr {
+        Expr::Member(field) => Ok(field),
+        _ => Err(unsupported("expected a field name")),
+    }
+}
+
+fn value(expr: Expr) -> Result<(String, ValueKind), FilterError> {
+    match expr {
+        Expr::Literal(Literal::Null) => Err(unsupported("`null` is not supported")),
+        Expr::Literal(Literal::Bool(value)) => Ok((value.to_string(), ValueKind::Boolean)),
+        Expr::Literal(Literal::Int(value)) => Ok((value.to_string(), ValueKind::Number)),
+        Expr::Literal(Literal::Float(value)) => Ok((format!("{:?}", value), ValueKind::Number)),
+        Expr::Literal(Literal::String(value)) => Ok((value, ValueKind::String)),
+        Expr::Literal(Literal::Date(value)) => Ok((value, ValueKind::Date)),
+        Expr::Literal(Literal::DateTimeOffset(value)) => Ok((value, ValueKind::DateTimeOffset)),
+        Expr::Literal(Literal::Guid(value)) => Ok((value, ValueKind::Guid)),
+        _ => Err(unsupported("expected a literal value")),
+    }
+}
+
+fn unsupported_expression() -> FilterError {
+    unsupported("expected a comparison between a field and a value")
+}
+
+fn unsupported(message: &str) -> FilterError {
+    FilterError::Unsupported {
+        message: message.to_string(),
+    }
+}
@@ -0,0 +1,226 @@
+//!
+//! The form_body module reads `application/x-www-form-urlencoded` request bodies into a [`QueryMap`].
+//! You need to enable the feature `form-body` to access these parsers.
+//!
+//! Bodies are decoded incrementally: only the pair that is currently being read is kept in memory.
+//!
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_io::AsyncRead;
+use std::{collections::HashMap, error::Error, fmt, future::poll_fn, io, pin::Pin};
+
+use crate::{ParseError, ParseLimits, QueryMap, QueryTokenizer};
+
+/// Default limit in bytes for form bodies
+pub const DEFAULT_BODY_LIMIT: usize = 262_144;
+
+const READ_BUFFER_SIZE: usize = 8192;
+
+/// Error returned when a form body cannot be read
+#[derive(Debug)]
+pub enum FormBodyError {
+    /// The body exceeds the configured limits
+    Limit(ParseError),
+    /// The underlying reader or stream failed
+    Io(io::Error),
+}
+
+impl fmt::Display for FormBodyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormBodyError::Limit(err) => write!(f, "invalid form body: {}", err),
+            FormBodyError::Io(err) => write!(f, "error reading form body: {}", err),
+        }
+    }
+}
+
+impl Error for FormBodyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FormBodyError::Limit(err) => Some(err),
+            FormBodyError::Io(err) => Some(err),
+        }
+    }
+}
+
+impl From<ParseError> for FormBodyError {
+    fn from(err: ParseError) -> Self {
+        FormBodyError::Limit(err)
+    }
+}
+
+impl From<io::Error> for FormBodyError {
+    fn from(err: io::Error) -> Self {
+        FormBodyError::Io(err)
+    }
+}
+
+/// Incremental parser for `application/x-www-form-urlencoded` bodies.
+///
+/// Feed the body in chunks of any size with [`FormBodyParser::push`],
+/// and call [`FormBodyParser::finish`] once the body has been fully read.
+pub struct FormBodyParser {
+    tokenizer: QueryTokenizer,
+    map: HashMap<String, Vec<String>>,
+    limit: usize,
+    read: usize,
+}
+
+impl FormBodyParser {
+    /// Create a parser that accepts at most `limit` bytes of body
+    #[must_use]
+    pub fn new(limit: usize, limits: ParseLimits) -> Self {
+        FormBodyParser {
+            tokenizer: QueryTokenizer::with_limits(limits),
+            map: HashMap::new(),
+            limit,
+            read: 0,
+        }
+    }
+
+    /// Parse the next chunk of the body
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), ParseError> {
+        self.read = self.read.saturating_add(chunk.len());
+        if self.read > self.limit {
+            return Err(ParseError::TooLarge { limit: self.limit });
+        }
+
+        self.tokenizer.feed(chunk);
+        self.drain()
+    }
+
+    /// Parse the remaining pair and return the collected [`QueryMap`]
+    pub fn finish(mut self) -> Result<QueryMap, ParseError> {
+        self.tokenizer.finish();
+        self.drain()?;
+        Ok(QueryMap::from(self.map))
+    }
+
+    fn drain(&mut self) -> Result<(), ParseError> {
+        while let Some(pair) = self.tokenizer.next_pair() {
+            let (k, v) = pair?;
+            self.map.entry(k).or_default().push(v);
+        }
+        Ok(())
+    }
+}
+
+/// Read a form body from an [`AsyncRead`] into a [`QueryMap`]
+pub async fn read_form<R>(
+    mut reader: R,
+    limit: usize,
+    limits: ParseLimits,
+) -> Result<QueryMap, FormBodyError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut parser = FormBodyParser::new(limit, limits);
+    let mut buf = [0u8; READ_BUFFER_SIZE];
+    loop {
+        let read = poll_fn(|cx| Pin::new(&mut reader).poll_read(cx, &mut buf)).await?;
+        if read == 0 {
+            break;
+        }
+        parser.push(&buf[..read])?;
+    }
+    Ok(parser.finish()?)
+}
+
+/// Read a form body from a [`Stream`] of [`Bytes`] into a [`QueryMap`]
+pub async fn read_form_stream<S, E>(
+    mut stream: S,
+    limit: usize,
+    limits: ParseLimits,
+) -> Result<QueryMap, FormBodyError>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Into<Box<dyn Error + Send + Sync>>,
+{
+    let mut parser = FormBodyParser::new(limit, limits);
+    while let Some(chunk) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+        let chunk = chunk.map_err(io::Error::other)?;
+        parser.push(&chunk)?;
+    }
+    Ok(parser.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::stream;
+
+    #[test]
+    fn test_parser_handles_split_pairs() {
+        let mut parser = FormBodyParser::new(DEFAULT_BODY_LIMIT, ParseLimits::default());
+        for chunk in ["fo", "o=b%", "20ar&baz", "=quux&", "foo=qux"] {
+            parser.push(chunk.as_bytes()).unwrap();
+        }
+        let map = parser.finish().unwrap();
+
+        assert_eq!(vec!["b ar", "qux"], map.all("foo").unwrap());
+        assert_eq!("quux", map.first("baz").unwrap());
+    }
+
+    #[test]
+    fn test_parser_enforces_limits() {
+        let mut parser = FormBodyParser::new(8, ParseLimits::default());
+        assert_eq!(
+            Err(ParseError::TooLarge { limit: 8 }),
+            parser.push(b"foo=bar&baz")
+        );
+
+        let limits = ParseLimits {
+            max_pair_length: 4,
+            ..ParseLimits::default()
+        };
+        let mut parser = FormBodyParser::new(DEFAULT_BODY_LIMIT, limits);
+        parser.push(b"a=1&foo").unwrap();
+        assert_eq!(
+            Err(ParseError::PairTooLong { limit: 4 }),
+            parser.push(b"=bar")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_read_form() {
+        let body: &[u8] = b"foo=bar+baz&foo=qux";
+        let map = read_form(body, DEFAULT_BODY_LIMIT, ParseLimits::default())
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["bar baz", "qux"], map.all("foo").unwrap());
+    }
+
+    #[actix_rt::test]
+    async fn test_read_form_stream() {
+        let chunks = vec![
+            Ok::<_, io::Error>(Bytes::from_static(b"foo=bar&ba")),
+            Ok(Bytes::from_static(b"z=quux")),
+        ];
+        let map = read_form_stream(
+            stream::iter(chunks),
+            DEFAULT_BODY_LIMIT,
+            ParseLimits::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!("bar", map.first("foo").unwrap());
+        assert_eq!("quux", map.first("baz").unwrap());
+
+        let limits = ParseLimits {
+            max_pairs: 1,
+            ..ParseLimits::default()
+        };
+        let chunks = vec![Ok::<_, io::Error>(Bytes::from_static(b"foo=bar&baz=quux"))];
+        let err = read_form_stream(stream::iter(chunks), DEFAULT_BODY_LIMIT, limits)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            FormBodyError::Limit(ParseError::TooManyPairs { limit: 1 })
+        ));
+    }
+}
@@ -0,0 +1,430 @@
+//!
+//! The jsonapi module reads [JSON:API](https://jsonapi.org/format/#fetching-sorting) style
+//! `sort` and sparse fieldset params from a [`QueryMap`].
+//!
+//! ```
+//! use query_map::{jsonapi::{Fieldsets, Sort, SortDirection}, QueryMap};
+//!
+//! let map: QueryMap = vec![
+//!     ("sort".to_string(), "-created_at,name".to_string()),
+//!     ("fields[articles]".to_string(), "title,body".to_string()),
+//! ]
+//! .into_iter()
+//! .collect();
+//!
+//! let sort = Sort::parse(&map).unwrap();
+//! assert_eq!(SortDirection::Descending, sort.keys()[0].direction);
+//! assert!(sort.validate(&["created_at", "name"]).is_ok());
+//!
+//! let fieldsets = Fieldsets::parse(&map).unwrap();
+//! assert_eq!(Some(&["title".to_string(), "body".to_string()][..]), fieldsets.fields("articles"));
+//! ```
+//!
+
+use std::{collections::BTreeMap, error::Error, fmt, sync::Arc};
+
+use crate::QueryMap;
+
+/// Name of the sort param
+pub const SORT_PARAM: &str = "sort";
+
+/// Prefix of the sparse fieldset params
+pub const FIELDS_PARAM: &str = "fields";
+
+/// Error returned when sort or fieldset params are not valid
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JsonApiError {
+    /// The param contains an empty field name
+    EmptyField {
+        /// Param name
+        param: String,
+    },
+    /// The field name is malformed, like a sort field with more than one `-` prefix
+    InvalidField {
+        /// Param name
+        param: String,
+        /// The rejected field
+        field: String,
+    },
+    /// The field is not in the allowlist
+    UnknownField {
+        /// Param name
+        param: String,
+        /// The rejected field
+        field: String,
+    },
+    /// The resource type is not in the allowlist
+    UnknownType {
+        /// The rejected resource type
+        type_name: String,
+    },
+}
+
+impl fmt::Display for JsonApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonApiError::EmptyField { param } => write!(f, "`{}` contains an empty field", param),
+            JsonApiError::InvalidField { param, field } => {
+                write!(f, "`{}` contains an invalid field `{}`", param, field)
+            }
+            JsonApiError::UnknownField { param, field } => {
+                write!(f, "`{}` contains an unknown field `{}`", param, field)
+            }
+            JsonApiError::UnknownType { type_name } => {
+                write!(f, "unknown resource type `{}`", type_name)
+            }
+        }
+    }
+}
+
+impl Error for JsonApiError {}
+
+/// Direction of a sort key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortDirection {
+    /// Ascending order, written without prefix
+    Ascending,
+    /// Descending order, written with a `-` prefix
+    Descending,
+}
+
+/// A field and the direction to sort it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortKey {
+    /// Field name
+    pub field: String,
+    /// Sort direction
+    pub direction: SortDirection,
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.direction == SortDirection::Descending {
+            f.write_str("-")?;
+        }
+        f.write_str(&self.field)
+    }
+}
+
+/// Sort keys read from the `sort` param, in order of precedence
+///
+/// `Display` writes the value of the `sort` param.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Sort(Vec<SortKey>);
+
+impl Sort {
+    /// Create a sort from a list of keys
+    #[must_use]
+    pub fn new(keys: Vec<SortKey>) -> Self {
+        Sort(keys)
+    }
+
+    /// Read the `sort` param from a [`QueryMap`].
+    ///
+    /// Repeated `sort` params are read in order, as if they were separated by commas.
+    pub fn parse(map: &QueryMap) -> Result<Sort, JsonApiError> {
+        let mut keys = Vec::new();
+        for value in map.all(SORT_PARAM).unwrap_or_default() {
+            for field in split_fields(SORT_PARAM, value)? {
+                let key = match field.strip_prefix('-') {
+                    Some(field) => SortKey {
+                        field: field.to_string(),
+                        direction: SortDirection::Descending,
+                    },
+                    None => SortKey {
+                        field: field.to_string(),
+                        direction: SortDirection::Ascending,
+                    },
+                };
+                if key.field.is_empty() {
+                    return Err(empty_field(SORT_PARAM));
+                }
+                if key.field.starts_with('-') {
+                    return Err(JsonApiError::InvalidField {
+                        param: SORT_PARAM.to_string(),
+                        field: field.to_string(),
+                    });
+                }
+                keys.push(key);
+            }
+        }
+        Ok(Sort(keys))
+    }
+
+    /// Return the sort keys
+    #[must_use]
+    pub fn keys(&self) -> &[SortKey] {
+        &self.0
+    }
+
+    /// Return true if there are no sort keys
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Reject fields that are not in `allowed`
+    pub fn validate(&self, allowed: &[&str]) -> Result<(), JsonApiError> {
+        match self
+            .0
+            .iter()
+            .find(|key| !allowed.contains(&key.field.as_str()))
+        {
+            Some(key) => Err(JsonApiError::UnknownField {
+                param: SORT_PARAM.to_string(),
+                field: key.field.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Return a copy of `map` where the `sort` param is replaced by these keys
+    #[must_use]
+    pub fn apply(&self, map: &QueryMap) -> QueryMap {
+        let mut data = (*map.0).clone();
+        data.remove(SORT_PARAM);
+        if !self.0.is_empty() {
+            data.insert(SORT_PARAM.to_string(), vec![self.to_string()]);
+        }
+        QueryMap(Arc::new(data))
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, key) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Fields requested for each resource type with `fields[type]` params
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Fieldsets(BTreeMap<String, Vec<String>>);
+
+impl Fieldsets {
+    /// Read the `fields[type]` params from a [`QueryMap`].
+    ///
+    /// An empty value requests no fields for that type.
+    pub fn parse(map: &QueryMap) -> Result<Fieldsets, JsonApiError> {
+        let mut fieldsets = BTreeMap::new();
+        for key in map.keys() {
+            let type_name = match fieldset_type(key) {
+                Some(type_name) => type_name,
+                None => continue,
+            };
+            let fields: &mut Vec<String> = fieldsets.entry(type_name.to_string()).or_default();
+            for value in map.all(key).unwrap_or_default() {
+                if value.is_empty() {
+                    continue;
+                }
+                for field in split_fields(key, value)? {
+                    if !fields.iter().any(|f| f == field) {
+                        fields.push(field.to_string());
+                    }
+                }
+            }
+        }
+        Ok(Fieldsets(fieldsets))
+    }
+
+    /// Return the fields requested for a resource type, `None` if all fields were requested
+    #[must_use]
+    pub fn fields(&self, type_name: &str) -> Option<&[String]> {
+        self.0.get(type_name).map(Vec::as_slice)
+    }
+
+    /// Set the fields requested for a resource type
+    pub fn insert(&mut self, type_name: impl Into<String>, fields: Vec<String>) {
+        self.0.insert(type_name.into(), fields);
+    }
+
+    /// Return true if there are no fieldsets
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Reject resource types and fields that are not in `allowed`,
+    /// a list of resource types with their fields
+    pub fn validate(&self, allowed: &[(&str, &[&str])]) -> Result<(), JsonApiError> {
+        for (type_name, fields) in &self.0 {
+            let allowed_fields = allowed
+                .iter()
+                .find(|(name, _)| name == type_name)
+                .map(|(_, fields)| *fields)
+                .ok_or_else(|| JsonApiError::UnknownType {
+                    type_name: type_name.clone(),
+                })?;
+            if let Some(field) = fields
+                .iter()
+                .find(|field| !allowed_fields.contains(&field.as_str()))
+            {
+                return Err(JsonApiError::UnknownField {
+                    param: fieldset_param(type_name),
+                    field: field.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Return a copy of `map` where the `fields[type]` params are replaced by these fieldsets
+    #[must_use]
+    pub fn apply(&self, map: &QueryMap) -> QueryMap {
+        let mut data = (*map.0).clone();
+        data.retain(|key, _| fieldset_type(key).is_none());
+        for (type_name, fields) in &self.0 {
+            data.insert(fieldset_param(type_name), vec![fields.join(",")]);
+        }
+        QueryMap(Arc::new(data))
+    }
+}
+
+fn fieldset_type(key: &str) -> Option<&str> {
+    key.strip_prefix(FIELDS_PARAM)?
+        .strip_prefix('[')?
+        .strip_suffix(']')
+        .filter(|type_name| !type_name.is_empty())
+}
+
+fn fieldset_param(type_name: &str) -> String {
+    format!("{}[{}]", FIELDS_PARAM, type_name)
+}
+
+fn split_fields<'a>(param: &str, value: &'a str) -> Result<Vec<&'a str>, JsonApiError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .map(|field| {
+            if field.is_empty() {
+                Err(empty_field(param))
+            } else {
+                Ok(field)
+            }
+        })
+        .collect()
+}
+
+fn empty_field(param: &str) -> JsonApiError {
+    JsonApiError::EmptyField {
+        param: param.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_map as map;
+
+    #[test]
+    fn test_parse_sort() {
+        let sort = Sort::parse(&map(&[("sort", "-created_at,name"), ("sort", "id")])).unwrap();
+        assert_eq!(
+            vec![
+                SortKey {
+                    field: "created_at".into(),
+                    direction: SortDirection::Descending
+                },
+                SortKey {
+                    field: "name".into(),
+                    direction: SortDirection::Ascending
+                },
+                SortKey {
+                    field: "id".into(),
+                    direction: SortDirection::Ascending
+                },
+            ],
+            sort.keys()
+        );
+        assert_eq!("-created_at,name,id", sort.to_string());
+
+        assert!(Sort::parse(&map(&[])).unwrap().is_empty());
+        assert_eq!(
+            Err(JsonApiError::EmptyField {
+                param: "sort".into()
+            }),
+            Sort::parse(&map(&[("sort", "name,-")]))
+        );
+        let err = Sort::parse(&map(&[("sort", "--name")])).unwrap_err();
+        assert_eq!(
+            JsonApiError::InvalidField {
+                param: "sort".into(),
+                field: "--name".into()
+            },
+            err
+        );
+        assert_eq!("`sort` contains an invalid field `--name`", err.to_string());
+    }
+
+    #[test]
+    fn test_validate_sort() {
+        let sort = Sort::parse(&map(&[("sort", "name,-secret")])).unwrap();
+        assert_eq!(
+            Err(JsonApiError::UnknownField {
+                param: "sort".into(),
+                field: "secret".into()
+            }),
+            sort.validate(&["name"])
+        );
+    }
+
+    #[test]
+    fn test_parse_fieldsets() {
+        let fieldsets = Fieldsets::parse(&map(&[
+            ("fields[articles]", "title,body"),
+            ("fields[people]", ""),
+            ("fields[]", "ignored"),
+            ("include", "author"),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            Some(&["title".to_string(), "body".to_string()][..]),
+            fieldsets.fields("articles")
+        );
+        assert_eq!(Some(&[][..]), fieldsets.fields("people"));
+        assert_eq!(None, fieldsets.fields("comments"));
+
+        assert_eq!(
+            Ok(()),
+            fieldsets.validate(&[("articles", &["title", "body"]), ("people", &[])])
+        );
+        assert_eq!(
+            Err(JsonApiError::UnknownField {
+                param: "fields[articles]".into(),
+                field: "body".into()
+            }),
+            fieldsets.validate(&[("articles", &["title"]), ("people", &[])])
+        );
+        assert_eq!(
+            Err(JsonApiError::UnknownType {
+                type_name: "people".into()
+            }),
+            fieldsets.validate(&[("articles", &["title", "body"])])
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let data = map(&[("sort", "a"), ("fields[articles]", "title"), ("page", "2")]);
+
+        let sort = Sort::new(vec![SortKey {
+            field: "name".into(),
+            direction: SortDirection::Descending,
+        }]);
+        let mut fieldsets = Fieldsets::default();
+        fieldsets.insert("people", vec!["name".into(), "age".into()]);
+
+        let data = fieldsets.apply(&sort.apply(&data));
+        assert_eq!("-name", data.first("sort").unwrap());
+        assert_eq!("name,age", data.first("fields[people]").unwrap());
+        assert_eq!(None, data.first("fields[articles]"));
+        assert_eq!("2", data.first("page").unwrap());
+    }
+}
@@ -50,23 +50,68 @@
 //! Create a [`QueryMap`] from a query string (requires `url-query` feature):
 //!
 //! ```
+//! # #[cfg(feature = "url-query")]
+//! # {
 //! use query_map::QueryMap;
 //!
 //! let data = "foo=bar&baz=quux&foo=qux";
 //! let map = data.parse::<QueryMap>().unwrap();
 //! let got = map.all("foo").unwrap();
 //! assert_eq!(vec!["bar", "qux"], got);
+//! # }
 //! ```
 //!
 
 use std::{
-    collections::{hash_map::Keys, HashMap},
+    collections::{
+        hash_map::{
+            Entry::{Occupied, Vacant},
+            Keys,
+        },
+        HashMap,
+    },
+    iter::FromIterator,
     sync::Arc,
 };
 
+mod cookie;
+pub use cookie::CookieError;
+
+mod limits;
+pub use limits::*;
+
+#[cfg(feature = "actix")]
+pub mod actix;
+
+pub mod filter;
+
+#[cfg(feature = "form-body")]
+pub mod form_body;
+
+pub mod jsonapi;
+
+#[cfg(feature = "multipart")]
+pub mod multipart;
+
+#[cfg(feature = "oauth1")]
+pub mod oauth1;
+
+pub mod odata;
+
+pub mod pagination;
+
+mod redact;
+pub use redact::*;
+
+#[cfg(feature = "schemars")]
+mod schema;
+
 #[cfg(feature = "serde")]
 pub mod serde;
 
+#[cfg(feature = "signing")]
+pub mod signing;
+
 #[cfg(feature = "serde")]
 pub use serde::standard::*;
 
@@ -80,10 +125,13 @@ mod url_query;
 #[cfg(feature = "url-query")]
 pub use url_query::*;
 
+pub mod validation;
+
 /// A read-only view into a map of data which may contain multiple values
 ///
-/// Internally data is always represented as many values
-#[derive(Default, Debug, PartialEq)]
+/// Internally data is always represented as many values.
+/// `Debug` and `Display` redact sensitive values, see [`RedactionPolicy`].
+#[derive(Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize), serde(crate = "serde_crate"))]
 pub struct QueryMap(pub(crate) Arc<HashMap<String, Vec<String>>>);
 
@@ -141,6 +189,23 @@ impl From<HashMap<String, Vec<String>>> for QueryMap {
     }
 }
 
+impl FromIterator<(String, String)> for QueryMap {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for (k, v) in iter {
+            match map.entry(k) {
+                Occupied(entry) => {
+                    entry.into_mut().push(v);
+                }
+                Vacant(entry) => {
+                    entry.insert(vec![v]);
+                }
+            };
+        }
+        QueryMap(Arc::new(map))
+    }
+}
+
 impl From<HashMap<String, String>> for QueryMap {
     fn from(inner: HashMap<String, String>) -> Self {
         // A `HashMap` cannot have repeated (key, value) pairs
@@ -208,6 +273,15 @@ impl<'a> Iterator for QueryMapKeys<'a> {
     }
 }
 
+/// Build a [`QueryMap`] from string pairs in tests
+#[cfg(test)]
+pub(crate) fn test_map(pairs: &[(&str, &str)]) -> QueryMap {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,81 @@
+use std::{error::Error, fmt};
+
+/// Limits applied while parsing untrusted input into a [`QueryMap`](crate::QueryMap)
+///
+/// The default limits are unbounded, which matches the behavior of `str::parse`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Maximum number of key-value pairs accepted
+    pub max_pairs: usize,
+    /// Maximum length in bytes of a single encoded key-value pair
+    pub max_pair_length: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            max_pairs: usize::MAX,
+            max_pair_length: usize::MAX,
+        }
+    }
+}
+
+#[cfg(any(feature = "url-query", feature = "multipart"))]
+impl ParseLimits {
+    pub(crate) fn check_pairs(&self, pairs: usize) -> Result<(), ParseError> {
+        if pairs > self.max_pairs {
+            return Err(ParseError::TooManyPairs {
+                limit: self.max_pairs,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_pair_length(&self, length: usize) -> Result<(), ParseError> {
+        if length > self.max_pair_length {
+            return Err(ParseError::PairTooLong {
+                limit: self.max_pair_length,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when the input exceeds the configured limits
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The whole input is larger than the limit in bytes
+    TooLarge {
+        /// Configured limit in bytes
+        limit: usize,
+    },
+    /// The input contains more pairs than allowed
+    TooManyPairs {
+        /// Configured maximum number of pairs
+        limit: usize,
+    },
+    /// A single pair is longer than allowed
+    PairTooLong {
+        /// Configured limit in bytes
+        limit: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::TooLarge { limit } => {
+                write!(f, "input is larger than the limit of {} bytes", limit)
+            }
+            ParseError::TooManyPairs { limit } => {
+                write!(f, "input contains more than {} pairs", limit)
+            }
+            ParseError::PairTooLong { limit } => {
+                write!(f, "pair is longer than the limit of {} bytes", limit)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
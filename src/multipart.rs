@@ -0,0 +1,418 @@
+//!
+//! The multipart module parses `multipart/form-data` bodies into a [`QueryMap`] of text fields.
+//! You need to enable the feature `multipart` to access this parser.
+//!
+//! File parts are not copied: they are exposed as [`FilePart`] metadata
+//! with the byte range of their content inside the original body.
+//!
+
+use std::{collections::HashMap, error::Error, fmt, ops::Range};
+
+use crate::{ParseError, ParseLimits, QueryMap};
+
+/// The result of parsing a `multipart/form-data` body
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MultipartForm {
+    /// Text fields, in the order they appear in the body for repeated names
+    pub fields: QueryMap,
+    /// File parts, in the order they appear in the body
+    pub files: Vec<FilePart>,
+}
+
+/// A part with a `filename` parameter in its `Content-Disposition` header
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilePart {
+    /// Name of the form field
+    pub name: String,
+    /// File name sent by the client
+    pub filename: String,
+    /// Value of the part's `Content-Type` header, if any
+    pub content_type: Option<String>,
+    /// Location of the file content inside the parsed body
+    pub range: Range<usize>,
+}
+
+impl FilePart {
+    /// Return the file content from the body this part was parsed from
+    #[must_use]
+    pub fn bytes<'a>(&self, body: &'a [u8]) -> &'a [u8] {
+        &body[self.range.clone()]
+    }
+}
+
+/// Error returned when a multipart body cannot be parsed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MultipartError {
+    /// The body exceeds the configured limits
+    Limit(ParseError),
+    /// The body does not follow the `multipart/form-data` format
+    Malformed(String),
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultipartError::Limit(err) => write!(f, "invalid multipart body: {}", err),
+            MultipartError::Malformed(reason) => write!(f, "malformed multipart body: {}", reason),
+        }
+    }
+}
+
+impl Error for MultipartError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MultipartError::Limit(err) => Some(err),
+            MultipartError::Malformed(_) => None,
+        }
+    }
+}
+
+impl From<ParseError> for MultipartError {
+    fn from(err: ParseError) -> Self {
+        MultipartError::Limit(err)
+    }
+}
+
+fn malformed<T>(reason: impl Into<String>) -> Result<T, MultipartError> {
+    Err(MultipartError::Malformed(reason.into()))
+}
+
+/// Extract the boundary parameter from a `multipart/form-data` content type
+///
+/// ```
+/// use query_map::multipart::boundary_from_content_type;
+///
+/// let boundary = boundary_from_content_type("multipart/form-data; boundary=\"abc\"");
+/// assert_eq!(Some("abc"), boundary);
+/// ```
+#[must_use]
+pub fn boundary_from_content_type(content_type: &str) -> Option<&str> {
+    let mut params = content_type.split(';');
+    let mime = params.next()?.trim();
+    if !mime.eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params.find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if name.trim().eq_ignore_ascii_case("boundary") {
+            let value = value.trim();
+            Some(
+                value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value),
+            )
+        } else {
+            None
+        }
+    })
+}
+
+/// Parse a `multipart/form-data` body delimited by `boundary`.
+///
+/// `limits.max_pairs` caps the number of parts, and
+/// `limits.max_pair_length` caps the size of each text field.
+///
+/// ```
+/// use query_map::{multipart, ParseLimits};
+///
+/// let body = b"--xyz\r\n\
+///     Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+///     hello\r\n\
+///     --xyz\r\n\
+///     Content-Disposition: form-data; name=\"upload\"; filename=\"a.txt\"\r\n\
+///     Content-Type: text/plain\r\n\r\n\
+///     file content\r\n\
+///     --xyz--\r\n";
+///
+/// let form = multipart::parse(body, "xyz", ParseLimits::default()).unwrap();
+/// assert_eq!("hello", form.fields.first("title").unwrap());
+/// assert_eq!(b"file content", form.files[0].bytes(body));
+/// ```
+pub fn parse(
+    body: &[u8],
+    boundary: &str,
+    limits: ParseLimits,
+) -> Result<MultipartForm, MultipartError> {
+    if boundary.is_empty() {
+        return malformed("empty boundary");
+    }
+    let delimiter = format!("\r\n--{}", boundary).into_bytes();
+
+    let mut pos = if body.starts_with(&delimiter[2..]) {
+        delimiter.len() - 2
+    } else {
+        match find(body, &delimiter) {
+            Some(idx) => idx + delimiter.len(),
+            None => return malformed("missing opening boundary"),
+        }
+    };
+
+    let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+    let mut files = Vec::new();
+    let mut count = 0;
+
+    loop {
+        let rest = &body[pos..];
+        if rest.starts_with(b"--") {
+            break;
+        }
+        // skip transport padding after the boundary
+        let padding = rest
+            .iter()
+            .take_while(|b| **b == b' ' || **b == b'\t')
+            .count();
+        if !rest[padding..].starts_with(b"\r\n") {
+            return malformed("expected a line break after the boundary");
+        }
+        pos += padding + 2;
+
+        let (headers, content_start) = split_headers(body, pos)?;
+        let content_len = match find(&body[content_start..], &delimiter) {
+            Some(len) => len,
+            None => return malformed("missing closing boundary"),
+        };
+        let range = content_start..content_start + content_len;
+        pos = range.end + delimiter.len();
+
+        count += 1;
+        limits.check_pairs(count)?;
+
+        let part = PartHeaders::parse(headers)?;
+        match part.filename {
+            Some(filename) => files.push(FilePart {
+                name: part.name,
+                filename,
+                content_type: part.content_type,
+                range,
+            }),
+            None => {
+                limits.check_pair_length(range.len())?;
+                let value = match String::from_utf8(body[range].to_vec()) {
+                    Ok(value) => value,
+                    Err(_) => return malformed(format!("field `{}` is not UTF-8", part.name)),
+                };
+                fields.entry(part.name).or_default().push(value);
+            }
+        }
+    }
+
+    Ok(MultipartForm {
+        fields: QueryMap::from(fields),
+        files,
+    })
+}
+
+/// Split the headers of the part starting at `pos` from its content
+fn split_headers(body: &[u8], pos: usize) -> Result<(&str, usize), MultipartError> {
+    let rest = &body[pos..];
+    let (headers, len) = if rest.starts_with(b"\r\n") {
+        (&rest[..0], 2)
+    } else {
+        match find(rest, b"\r\n\r\n") {
+            Some(end) => (&rest[..end], end + 4),
+            None => return malformed("missing end of part headers"),
+        }
+    };
+    match std::str::from_utf8(headers) {
+        Ok(headers) => Ok((headers, pos + len)),
+        Err(_) => malformed("part headers are not UTF-8"),
+    }
+}
+
+struct PartHeaders {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+}
+
+impl PartHeaders {
+    fn parse(headers: &str) -> Result<Self, MultipartError> {
+        let mut disposition = None;
+        let mut content_type = None;
+        for line in headers.split("\r\n").filter(|line| !line.is_empty()) {
+            let (name, value) = match line.split_once(':') {
+                Some(header) => header,
+                None => return malformed(format!("invalid part header `{}`", line)),
+            };
+            if name.trim().eq_ignore_ascii_case("content-disposition") {
+                disposition = Some(value.trim());
+            } else if name.trim().eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.trim().to_string());
+            }
+        }
+
+        let disposition = match disposition {
+            Some(disposition) => disposition,
+            None => return malformed("part without Content-Disposition header"),
+        };
+        let mut params = split_params(disposition).into_iter();
+        if !params
+            .next()
+            .is_some_and(|kind| kind.eq_ignore_ascii_case("form-data"))
+        {
+            return malformed("part is not form-data");
+        }
+
+        let mut name = None;
+        let mut filename = None;
+        for param in params {
+            if let Some((key, value)) = param.split_once('=') {
+                let value = unquote(value.trim());
+                match key.trim().to_ascii_lowercase().as_str() {
+                    "name" => name = Some(value),
+                    "filename" => filename = Some(value),
+                    _ => {}
+                }
+            }
+        }
+
+        match name {
+            Some(name) => Ok(PartHeaders {
+                name,
+                filename,
+                content_type,
+            }),
+            None => malformed("part without name"),
+        }
+    }
+}
+
+/// Split a header value on `;`, ignoring separators inside quoted strings
+fn split_params(value: &str) -> Vec<&str> {
+    let mut params = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (idx, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                params.push(value[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    params.push(value[start..].trim());
+    params
+}
+
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => {
+            let mut unquoted = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => unquoted.extend(chars.next()),
+                    c => unquoted.push(c),
+                }
+            }
+            unquoted
+        }
+        None => value.to_string(),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = b"preamble\r\n\
+        --boundary\r\n\
+        Content-Disposition: form-data; name=\"foo\"\r\n\r\n\
+        bar\r\n\
+        --boundary\r\n\
+        content-disposition: form-data; name=\"foo\"\r\n\r\n\
+        qux\r\n\
+        --boundary\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a;b.txt\"\r\n\
+        Content-Type: text/plain\r\n\r\n\
+        line 1\r\nline 2\r\n\
+        --boundary\r\n\
+        Content-Disposition: form-data; name=\"empty\"\r\n\r\n\
+        \r\n\
+        --boundary--\r\n\
+        epilogue";
+
+    #[test]
+    fn test_parse_fields_and_files() {
+        let form = parse(BODY, "boundary", ParseLimits::default()).unwrap();
+
+        assert_eq!(vec!["bar", "qux"], form.fields.all("foo").unwrap());
+        assert_eq!("", form.fields.first("empty").unwrap());
+        assert_eq!(None, form.fields.first("file"));
+
+        assert_eq!(1, form.files.len());
+        let file = &form.files[0];
+        assert_eq!("file", file.name);
+        assert_eq!("a;b.txt", file.filename);
+        assert_eq!(Some("text/plain"), file.content_type.as_deref());
+        assert_eq!(b"line 1\r\nline 2", file.bytes(BODY));
+    }
+
+    #[test]
+    fn test_parse_enforces_limits() {
+        let limits = ParseLimits {
+            max_pairs: 3,
+            ..ParseLimits::default()
+        };
+        assert_eq!(
+            Err(MultipartError::Limit(ParseError::TooManyPairs { limit: 3 })),
+            parse(BODY, "boundary", limits)
+        );
+
+        let limits = ParseLimits {
+            max_pair_length: 2,
+            ..ParseLimits::default()
+        };
+        assert_eq!(
+            Err(MultipartError::Limit(ParseError::PairTooLong { limit: 2 })),
+            parse(BODY, "boundary", limits)
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed_bodies() {
+        let err = parse(BODY, "other", ParseLimits::default()).unwrap_err();
+        assert_eq!(
+            MultipartError::Malformed("missing opening boundary".into()),
+            err
+        );
+
+        let body = b"--b\r\nContent-Disposition: form-data; name=\"foo\"\r\n\r\nbar";
+        let err = parse(body, "b", ParseLimits::default()).unwrap_err();
+        assert_eq!(
+            MultipartError::Malformed("missing closing boundary".into()),
+            err
+        );
+
+        let body = b"--b\r\nContent-Type: text/plain\r\n\r\nbar\r\n--b--";
+        let err = parse(body, "b", ParseLimits::default()).unwrap_err();
+        assert_eq!(
+            MultipartError::Malformed("part without Content-Disposition header".into()),
+            err
+        );
+    }
+
+    #[test]
+    fn test_boundary_from_content_type() {
+        assert_eq!(
+            Some("----WebKitFormBoundary7MA4YWxk"),
+            boundary_from_content_type(
+                "multipart/form-data; boundary=----WebKitFormBoundary7MA4YWxk"
+            )
+        );
+        assert_eq!(None, boundary_from_content_type("text/plain; boundary=abc"));
+        assert_eq!(None, boundary_from_content_type("multipart/form-data"));
+    }
+}
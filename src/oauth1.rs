@@ -0,0 +1,212 @@
+//!
+//! The oauth1 module builds OAuth 1.0a signature base strings and HMAC-SHA1 signatures
+//! following [RFC 5849](https://www.rfc-editor.org/rfc/rfc5849#section-3.4).
+//! You need to enable the feature `oauth1` to access these functions.
+//!
+//! Pass the query params, the form body params and the `oauth_*` params as separate [`QueryMap`]s,
+//! they are merged before signing. Any `oauth_signature` param is ignored.
+//!
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha1::Sha1;
+
+use crate::QueryMap;
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// Characters encoded by RFC 3986, everything but unreserved characters
+const RFC3986: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+const SIGNATURE_PARAM: &str = "oauth_signature";
+
+/// Signature base string and HMAC-SHA1 signature of a request
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    /// The signature base string
+    pub base_string: String,
+    /// The base64 encoded HMAC-SHA1 signature, the value for `oauth_signature`
+    pub signature: String,
+}
+
+/// Build the base string for a request and sign it with HMAC-SHA1.
+///
+/// The signing key is built from the consumer secret and the token secret,
+/// use `None` when the request doesn't have a token yet.
+///
+/// ```
+/// use query_map::{oauth1, QueryMap};
+///
+/// let params: QueryMap = vec![("oauth_consumer_key".to_string(), "key".to_string())]
+///     .into_iter()
+///     .collect();
+///
+/// let signed = oauth1::sign("get", "HTTPS://Example.com:443/photos?size=large", &[&params], "secret", None);
+/// assert_eq!(
+///     "GET&https%3A%2F%2Fexample.com%2Fphotos&oauth_consumer_key%3Dkey",
+///     signed.base_string
+/// );
+/// ```
+#[must_use]
+pub fn sign(
+    method: &str,
+    base_url: &str,
+    params: &[&QueryMap],
+    consumer_secret: &str,
+    token_secret: Option<&str>,
+) -> Signature {
+    let base_string = signature_base_string(method, base_url, params);
+    let signature = hmac_sha1_signature(&base_string, consumer_secret, token_secret);
+    Signature {
+        base_string,
+        signature,
+    }
+}
+
+/// Build the signature base string for a request.
+///
+/// The base URL is normalized: the scheme and host are lowercased,
+/// default ports are removed, and any query or fragment is dropped.
+/// Pass the query of the URL as one of the `params` maps.
+#[must_use]
+pub fn signature_base_string(method: &str, base_url: &str, params: &[&QueryMap]) -> String {
+    format!(
+        "{}&{}&{}",
+        encode(&method.to_ascii_uppercase()),
+        encode(&normalize_base_url(base_url)),
+        encode(&normalize_params(params))
+    )
+}
+
+/// Sign a base string with HMAC-SHA1, and return the signature encoded in base64
+#[must_use]
+pub fn hmac_sha1_signature(
+    base_string: &str,
+    consumer_secret: &str,
+    token_secret: Option<&str>,
+) -> String {
+    let key = format!(
+        "{}&{}",
+        encode(consumer_secret),
+        encode(token_secret.unwrap_or_default())
+    );
+    let mut mac = HmacSha1::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(base_string.as_bytes());
+    STANDARD.encode(mac.finalize().into_bytes())
+}
+
+/// Encode a value following RFC 3986
+#[must_use]
+pub fn encode(value: &str) -> String {
+    utf8_percent_encode(value, RFC3986).to_string()
+}
+
+/// Merge, encode and sort the params, and join them with `&`
+fn normalize_params(params: &[&QueryMap]) -> String {
+    let mut pairs = params
+        .iter()
+        .flat_map(|map| map.iter())
+        .filter(|(key, _)| *key != SIGNATURE_PARAM)
+        .map(|(key, value)| (encode(key), encode(value)))
+        .collect::<Vec<_>>();
+    pairs.sort_unstable();
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn normalize_base_url(url: &str) -> String {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let (scheme, rest) = match url.split_once("://") {
+        Some(parts) => parts,
+        None => return url.to_string(),
+    };
+    let scheme = scheme.to_ascii_lowercase();
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => rest.split_at(idx),
+        None => (rest, "/"),
+    };
+
+    let mut authority = authority.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" => Some(":80"),
+        "https" => Some(":443"),
+        _ => None,
+    };
+    if let Some(port) = default_port {
+        if authority.ends_with(port) {
+            authority.truncate(authority.len() - port.len());
+        }
+    }
+    format!("{}://{}{}", scheme, authority, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_map as map;
+
+    #[test]
+    fn test_normalize_base_url() {
+        assert_eq!(
+            "http://example.com/r%20v/X",
+            normalize_base_url("HTTP://Example.com:80/r%20v/X?id=123")
+        );
+        assert_eq!(
+            "https://www.example.net:8080/",
+            normalize_base_url("https://www.example.net:8080/?q=1#top")
+        );
+        assert_eq!(
+            "https://example.com/",
+            normalize_base_url("https://example.com:443")
+        );
+    }
+
+    #[test]
+    fn test_sign_request() {
+        let query = map(&[("include_entities", "true")]);
+        let body = map(&[(
+            "status",
+            "Hello Ladies + Gentlemen, a signed OAuth request!",
+        )]);
+        let oauth = map(&[
+            ("oauth_consumer_key", "xvz1evFS4wEEPTGEFPHBog"),
+            ("oauth_nonce", "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg"),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", "1318622958"),
+            (
+                "oauth_token",
+                "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb",
+            ),
+            ("oauth_version", "1.0"),
+            ("oauth_signature", "ignored"),
+        ]);
+
+        let signed = sign(
+            "post",
+            "https://api.twitter.com/1.1/statuses/update.json",
+            &[&query, &body, &oauth],
+            "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+            Some("LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE"),
+        );
+
+        assert_eq!(
+            "POST&https%3A%2F%2Fapi.twitter.com%2F1.1%2Fstatuses%2Fupdate.json&include_entities%3Dtrue%26oauth_consumer_key%3Dxvz1evFS4wEEPTGEFPHBog%26oauth_nonce%3DkYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg%26oauth_signature_method%3DHMAC-SHA1%26oauth_timestamp%3D1318622958%26oauth_token%3D370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb%26oauth_version%3D1.0%26status%3DHello%2520Ladies%2520%252B%2520Gentlemen%252C%2520a%2520signed%2520OAuth%2520request%2521",
+            signed.base_string
+        );
+        assert_eq!("hCtSmYh+iHYCEqBWrE7C7hYmtUk=", signed.signature);
+    }
+
+    #[test]
+    fn test_sort_repeated_keys_by_value() {
+        let params = map(&[("a", "2"), ("a", "1"), ("b", "~x y")]);
+        assert_eq!("a=1&a=2&b=~x%20y", normalize_params(&[&params]));
+    }
+}
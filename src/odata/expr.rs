@@ -0,0 +1,847 @@
+use crate::odata::{ODataError, ODataLimits};
+
+const FILTER_OPTION: &str = "$filter";
+
+/// A literal value in an expression
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    /// `null`
+    Null,
+    /// `true` or `false`
+    Bool(bool),
+    /// An integer number
+    Int(i64),
+    /// A decimal number
+    Float(f64),
+    /// A single quoted string, without quotes
+    String(String),
+    /// A date, like `2020-01-31`
+    Date(String),
+    /// A date and time with an offset, like `2020-01-31T12:00:00Z`
+    DateTimeOffset(String),
+    /// A GUID, like `01234567-89ab-cdef-0123-456789abcdef`
+    Guid(String),
+}
+
+/// Unary operators
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// Logical negation, `not`
+    Not,
+    /// Arithmetic negation, `-`
+    Neg,
+}
+
+/// Binary operators
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    /// `or`
+    Or,
+    /// `and`
+    And,
+    /// `eq`
+    Eq,
+    /// `ne`
+    Ne,
+    /// `gt`
+    Gt,
+    /// `ge`
+    Ge,
+    /// `lt`
+    Lt,
+    /// `le`
+    Le,
+    /// `has`
+    Has,
+    /// `add`
+    Add,
+    /// `sub`
+    Sub,
+    /// `mul`
+    Mul,
+    /// `div`
+    Div,
+    /// `divby`
+    DivBy,
+    /// `mod`
+    Mod,
+}
+
+impl BinaryOp {
+    fn from_word(word: &str) -> Option<BinaryOp> {
+        Some(match word.to_ascii_lowercase().as_str() {
+            "or" => BinaryOp::Or,
+            "and" => BinaryOp::And,
+            "eq" => BinaryOp::Eq,
+            "ne" => BinaryOp::Ne,
+            "gt" => BinaryOp::Gt,
+            "ge" => BinaryOp::Ge,
+            "lt" => BinaryOp::Lt,
+            "le" => BinaryOp::Le,
+            "has" => BinaryOp::Has,
+            "add" => BinaryOp::Add,
+            "sub" => BinaryOp::Sub,
+            "mul" => BinaryOp::Mul,
+            "div" => BinaryOp::Div,
+            "divby" => BinaryOp::DivBy,
+            "mod" => BinaryOp::Mod,
+            _ => return None,
+        })
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq | BinaryOp::Ne => 3,
+            BinaryOp::Gt | BinaryOp::Ge | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Has => 4,
+            BinaryOp::Add | BinaryOp::Sub => 5,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::DivBy | BinaryOp::Mod => 6,
+        }
+    }
+}
+
+/// Precedence of `in`, the same as relational operators
+const IN_PRECEDENCE: u8 = 4;
+
+/// Functions accepted in expressions, with their minimum and maximum number of arguments
+const FUNCTIONS: &[(&str, usize, usize)] = &[
+    ("ceiling", 1, 1),
+    ("concat", 2, 2),
+    ("contains", 2, 2),
+    ("day", 1, 1),
+    ("endswith", 2, 2),
+    ("floor", 1, 1),
+    ("hour", 1, 1),
+    ("indexof", 2, 2),
+    ("length", 1, 1),
+    ("matchespattern", 2, 2),
+    ("minute", 1, 1),
+    ("month", 1, 1),
+    ("round", 1, 1),
+    ("second", 1, 1),
+    ("startswith", 2, 2),
+    ("substring", 2, 3),
+    ("tolower", 1, 1),
+    ("toupper", 1, 1),
+    ("trim", 1, 1),
+    ("year", 1, 1),
+];
+
+/// A `$filter` expression
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    /// A literal value
+    Literal(Literal),
+    /// A property path, segments are separated by `/`
+    Member(String),
+    /// A unary operation
+    Unary {
+        /// The operator
+        op: UnaryOp,
+        /// The operand
+        expr: Box<Expr>,
+    },
+    /// A binary operation
+    Binary {
+        /// The operator
+        op: BinaryOp,
+        /// The left operand
+        left: Box<Expr>,
+        /// The right operand
+        right: Box<Expr>,
+    },
+    /// A membership test, `expr in (a, b)`
+    In {
+        /// The tested expression
+        expr: Box<Expr>,
+        /// The list of candidates
+        list: Vec<Expr>,
+    },
+    /// A function call, like `contains(Name, 'milk')`
+    Call {
+        /// Function name, in lowercase
+        function: String,
+        /// Arguments
+        args: Vec<Expr>,
+    },
+}
+
+/// Parse a `$filter` expression with the default [`ODataLimits`].
+///
+/// Errors report the byte offset in `input` where parsing failed.
+pub fn parse_filter(input: &str) -> Result<Expr, ODataError> {
+    parse_filter_with_limits(input, &ODataLimits::default())
+}
+
+/// Parse a `$filter` expression, rejecting input that exceeds the given limits
+pub fn parse_filter_with_limits(input: &str, limits: &ODataLimits) -> Result<Expr, ODataError> {
+    parse_expression(FILTER_OPTION, input, limits)
+}
+
+/// Parse an expression, reporting errors for `option`
+pub(crate) fn parse_expression(
+    option: &str,
+    input: &str,
+    limits: &ODataLimits,
+) -> Result<Expr, ODataError> {
+    let mut parser = Parser::new(option, input, limits)?;
+    let expr = parser.expression(0)?;
+    parser.end()?;
+    Ok(expr)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Number(&'a str),
+    Str(String),
+    Literal(Literal),
+    Minus,
+    Open,
+    Close,
+    Comma,
+    End,
+}
+
+pub(crate) struct Parser<'a> {
+    option: &'a str,
+    tokens: Vec<(usize, Token<'a>)>,
+    next: usize,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(
+        option: &'a str,
+        input: &'a str,
+        limits: &ODataLimits,
+    ) -> Result<Self, ODataError> {
+        Ok(Parser {
+            option,
+            tokens: tokenize(option, input)?,
+            next: 0,
+            depth: 0,
+            max_depth: limits.max_depth,
+        })
+    }
+
+    /// Parse operators with a precedence higher than `min_precedence`
+    pub(crate) fn expression(&mut self, min_precedence: u8) -> Result<Expr, ODataError> {
+        let mut left = self.unary()?;
+        loop {
+            let word = match self.peek() {
+                Token::Word(word) => *word,
+                _ => return Ok(left),
+            };
+
+            if word.eq_ignore_ascii_case("in") {
+                if IN_PRECEDENCE <= min_precedence {
+                    return Ok(left);
+                }
+                self.advance();
+                let list = self.list()?;
+                left = Expr::In {
+                    expr: Box::new(left),
+                    list,
+                };
+                continue;
+            }
+
+            let op = match BinaryOp::from_word(word) {
+                Some(op) if op.precedence() > min_precedence => op,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = self.expression(op.precedence())?;
+            left = Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+    }
+
+    /// Return the next word and its position, like `asc` or `desc` after an expression
+    pub(crate) fn word(&mut self) -> Option<(usize, &'a str)> {
+        match self.tokens[self.next] {
+            (position, Token::Word(word)) => {
+                self.advance();
+                Some((position, word))
+            }
+            _ => None,
+        }
+    }
+
+    /// Consume a `,` between list items, return false at the end of the input
+    pub(crate) fn separator(&mut self) -> Result<bool, ODataError> {
+        match self.advance() {
+            (_, Token::Comma) => Ok(true),
+            (_, Token::End) => Ok(false),
+            (position, _) => Err(self.error(position, "expected `,` or the end of the list")),
+        }
+    }
+
+    pub(crate) fn end(&mut self) -> Result<(), ODataError> {
+        match self.advance() {
+            (_, Token::End) => Ok(()),
+            (position, _) => Err(self.error(
+                position,
+                "expected an operator or the end of the expression",
+            )),
+        }
+    }
+
+    pub(crate) fn error(&self, position: usize, message: &str) -> ODataError {
+        syntax(self.option, position, message)
+    }
+
+    /// Enter a nested operand, like the operand of `not` or an expression in parentheses.
+    ///
+    /// Every recursion goes through an operand, so limiting their nesting
+    /// bounds the stack used by the parser.
+    fn enter(&mut self) -> Result<(), ODataError> {
+        if self.depth >= self.max_depth {
+            let position = self.tokens[self.next].0;
+            return Err(self.error(position, "expression is nested too deeply"));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn unary(&mut self) -> Result<Expr, ODataError> {
+        self.enter()?;
+        let expr = self.operand();
+        self.depth -= 1;
+        expr
+    }
+
+    fn operand(&mut self) -> Result<Expr, ODataError> {
+        match self.peek() {
+            Token::Word(word) if word.eq_ignore_ascii_case("not") => {
+                self.advance();
+                let expr = self.unary()?;
+                Ok(Expr::Unary {
+                    op: UnaryOp::Not,
+                    expr: Box::new(expr),
+                })
+            }
+            Token::Minus => {
+                self.advance();
+                match self.unary()? {
+                    Expr::Literal(Literal::Int(n)) => Ok(Expr::Literal(Literal::Int(-n))),
+                    Expr::Literal(Literal::Float(n)) => Ok(Expr::Literal(Literal::Float(-n))),
+                    expr => Ok(Expr::Unary {
+                        op: UnaryOp::Neg,
+                        expr: Box::new(expr),
+                    }),
+                }
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ODataError> {
+        let (position, token) = self.advance();
+        match token {
+            Token::Open => {
+                let expr = self.expression(0)?;
+                self.expect_close()?;
+                Ok(expr)
+            }
+            Token::Str(value) => Ok(Expr::Literal(Literal::String(value))),
+            Token::Literal(literal) => Ok(Expr::Literal(literal)),
+            Token::Number(number) => parse_number(number)
+                .ok_or_else(|| self.error(position, &format!("invalid number `{}`", number))),
+            Token::Word(word) if word.eq_ignore_ascii_case("null") => {
+                Ok(Expr::Literal(Literal::Null))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("true") => {
+                Ok(Expr::Literal(Literal::Bool(true)))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("false") => {
+                Ok(Expr::Literal(Literal::Bool(false)))
+            }
+            Token::Word(word) if self.peek() == &Token::Open => self.call(position, word),
+            Token::Word(word)
+                if BinaryOp::from_word(word).is_some() || word.eq_ignore_ascii_case("in") =>
+            {
+                Err(self.error(position, "expected an operand"))
+            }
+            Token::Word(word) => Ok(Expr::Member(word.to_string())),
+            Token::End => Err(self.error(position, "unexpected end of expression")),
+            _ => Err(self.error(position, "expected an operand")),
+        }
+    }
+
+    fn call(&mut self, position: usize, name: &str) -> Result<Expr, ODataError> {
+        let function = name.to_ascii_lowercase();
+        let (min, max) = FUNCTIONS
+            .iter()
+            .find(|(f, _, _)| *f == function)
+            .map(|(_, min, max)| (*min, *max))
+            .ok_or_else(|| self.error(position, &format!("unknown function `{}`", name)))?;
+
+        let args = self.list()?;
+        if args.len() < min || args.len() > max {
+            let expected = if min == max {
+                min.to_string()
+            } else {
+                format!("{} to {}", min, max)
+            };
+            return Err(self.error(
+                position,
+                &format!(
+                    "`{}` expects {} arguments, found {}",
+                    function,
+                    expected,
+                    args.len()
+                ),
+            ));
+        }
+        Ok(Expr::Call { function, args })
+    }
+
+    fn list(&mut self) -> Result<Vec<Expr>, ODataError> {
+        match self.advance() {
+            (_, Token::Open) => {}
+            (position, _) => return Err(self.error(position, "expected `(`")),
+        }
+        if self.peek() == &Token::Close {
+            self.advance();
+            return Ok(Vec::new());
+        }
+
+        let mut items = vec![self.expression(0)?];
+        loop {
+            match self.advance() {
+                (_, Token::Comma) => items.push(self.expression(0)?),
+                (_, Token::Close) => return Ok(items),
+                (position, _) => return Err(self.error(position, "expected `,` or `)`")),
+            }
+        }
+    }
+
+    fn expect_close(&mut self) -> Result<(), ODataError> {
+        match self.advance() {
+            (_, Token::Close) => Ok(()),
+            (position, _) => Err(self.error(position, "expected `)`")),
+        }
+    }
+
+    fn peek(&self) -> &Token<'a> {
+        &self.tokens[self.next].1
+    }
+
+    fn advance(&mut self) -> (usize, Token<'a>) {
+        let (position, token) = self.tokens[self.next].clone();
+        // the last token is always `End`, keep returning it
+        if token != Token::End {
+            self.next += 1;
+        }
+        (position, token)
+    }
+}
+
+pub(crate) fn syntax(option: &str, position: usize, message: &str) -> ODataError {
+    ODataError::Syntax {
+        option: option.to_string(),
+        position,
+        message: message.to_string(),
+    }
+}
+
+fn parse_number(number: &str) -> Option<Expr> {
+    let literal = if number.contains(['.', 'e', 'E']) {
+        Literal::Float(number.parse().ok()?)
+    } else {
+        Literal::Int(number.parse().ok()?)
+    };
+    Some(Expr::Literal(literal))
+}
+
+/// Return the value of `count` ASCII digits at `idx`
+fn digits(input: &[u8], idx: usize, count: usize) -> Option<u32> {
+    input.get(idx..idx + count)?.iter().try_fold(0, |n, b| {
+        b.is_ascii_digit().then(|| n * 10 + u32::from(b - b'0'))
+    })
+}
+
+fn is_guid(value: &str) -> bool {
+    let groups = value.split('-').map(str::len).collect::<Vec<_>>();
+    groups == [8, 4, 4, 4, 12] && value.bytes().all(|b| b == b'-' || b.is_ascii_hexdigit())
+}
+
+/// Return true for values starting like a date, `yyyy-`
+fn starts_like_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    digits(bytes, 0, 4).is_some() && bytes.get(4) == Some(&b'-')
+}
+
+/// Parse a `yyyy-mm-dd` date, or a date time offset like `yyyy-mm-ddThh:mm:ss.fffZ`,
+/// where seconds and fractional seconds are optional and the offset can also be `+hh:mm`
+fn parse_date(value: &str) -> Option<Literal> {
+    let bytes = value.as_bytes();
+    let date = bytes.len() >= 10
+        && starts_like_date(value)
+        && matches!(digits(bytes, 5, 2), Some(1..=12))
+        && bytes[7] == b'-'
+        && matches!(digits(bytes, 8, 2), Some(1..=31));
+    if !date {
+        return None;
+    }
+    if bytes.len() == 10 {
+        return Some(Literal::Date(value.to_string()));
+    }
+
+    let time = matches!(bytes[10], b'T' | b't')
+        && matches!(digits(bytes, 11, 2), Some(0..=23))
+        && bytes.get(13) == Some(&b':')
+        && matches!(digits(bytes, 14, 2), Some(0..=59));
+    if !time {
+        return None;
+    }
+    let mut idx = 16;
+    if bytes.get(idx) == Some(&b':') {
+        if !matches!(digits(bytes, idx + 1, 2), Some(0..=59)) {
+            return None;
+        }
+        idx += 3;
+        if bytes.get(idx) == Some(&b'.') {
+            let fraction = bytes[idx + 1..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+            if fraction == 0 {
+                return None;
+            }
+            idx += 1 + fraction;
+        }
+    }
+
+    let offset = &bytes[idx..];
+    let valid_offset = matches!(offset, [b'Z'] | [b'z'])
+        || (offset.len() == 6
+            && matches!(offset[0], b'+' | b'-')
+            && matches!(digits(offset, 1, 2), Some(0..=23))
+            && offset[3] == b':'
+            && matches!(digits(offset, 4, 2), Some(0..=59)));
+    valid_offset.then(|| Literal::DateTimeOffset(value.to_string()))
+}
+
+fn tokenize<'a>(option: &str, input: &'a str) -> Result<Vec<(usize, Token<'a>)>, ODataError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        // dates and GUIDs contain `-`, so they are read before numbers and words
+        if c.is_ascii_hexdigit() {
+            let rest = &input[start..];
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | ':' | '.' | '+')))
+                .unwrap_or(rest.len());
+            let value = &rest[..end];
+            let literal =
+                if is_guid(value) {
+                    Some(Literal::Guid(value.to_string()))
+                } else if starts_like_date(value) {
+                    Some(parse_date(value).ok_or_else(|| {
+                        syntax(option, start, &format!("invalid date `{}`", value))
+                    })?)
+                } else {
+                    None
+                };
+            if let Some(literal) = literal {
+                while chars.next_if(|(idx, _)| *idx < start + end).is_some() {}
+                tokens.push((start, Token::Literal(literal)));
+                continue;
+            }
+        }
+
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '-' => Token::Minus,
+            '\'' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) if chars.peek().map(|(_, c)| *c) == Some('\'') => {
+                            chars.next();
+                            value.push('\'');
+                        }
+                        Some((_, '\'')) => break,
+                        Some((_, c)) => value.push(c),
+                        None => return Err(syntax(option, start, "unterminated string")),
+                    }
+                }
+                Token::Str(value)
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = start + 1;
+                let mut prev = c;
+                while let Some((idx, c)) = chars.peek().copied() {
+                    let exponent_sign = matches!(c, '+' | '-') && matches!(prev, 'e' | 'E');
+                    if !(c.is_ascii_alphanumeric() || c == '.' || exponent_sign) {
+                        break;
+                    }
+                    end = idx + 1;
+                    prev = c;
+                    chars.next();
+                }
+                Token::Number(&input[start..end])
+            }
+            c if c.is_alphabetic() || c == '_' || c == '$' || c == '@' => {
+                let mut end = start + c.len_utf8();
+                while let Some((idx, c)) = chars.peek().copied() {
+                    if !(c.is_alphanumeric() || matches!(c, '_' | '/' | '.' | '$' | '@')) {
+                        break;
+                    }
+                    end = idx + c.len_utf8();
+                    chars.next();
+                }
+                Token::Word(&input[start..end])
+            }
+            c => {
+                return Err(syntax(
+                    option,
+                    start,
+                    &format!("unexpected character `{}`", c),
+                ))
+            }
+        };
+        tokens.push((start, token));
+    }
+    tokens.push((input.len(), Token::End));
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(name: &str) -> Box<Expr> {
+        Box::new(Expr::Member(name.into()))
+    }
+
+    fn int(n: i64) -> Box<Expr> {
+        Box::new(Expr::Literal(Literal::Int(n)))
+    }
+
+    fn binary(op: BinaryOp, left: Box<Expr>, right: Box<Expr>) -> Box<Expr> {
+        Box::new(Expr::Binary { op, left, right })
+    }
+
+    fn error(position: usize, message: &str) -> Result<Expr, ODataError> {
+        Err(syntax("$filter", position, message))
+    }
+
+    #[test]
+    fn test_precedence() {
+        let expr = parse_filter("a eq 1 or b add 2 mul 3 gt -4 and not c").unwrap();
+        let expected = binary(
+            BinaryOp::Or,
+            binary(BinaryOp::Eq, member("a"), int(1)),
+            binary(
+                BinaryOp::And,
+                binary(
+                    BinaryOp::Gt,
+                    binary(
+                        BinaryOp::Add,
+                        member("b"),
+                        binary(BinaryOp::Mul, int(2), int(3)),
+                    ),
+                    int(-4),
+                ),
+                Box::new(Expr::Unary {
+                    op: UnaryOp::Not,
+                    expr: member("c"),
+                }),
+            ),
+        );
+        assert_eq!(*expected, expr);
+    }
+
+    #[test]
+    fn test_left_associativity() {
+        let expr = parse_filter("a sub 1 sub 2").unwrap();
+        let expected = binary(
+            BinaryOp::Sub,
+            binary(BinaryOp::Sub, member("a"), int(1)),
+            int(2),
+        );
+        assert_eq!(*expected, expr);
+    }
+
+    #[test]
+    fn test_functions_and_literals() {
+        let expr =
+            parse_filter("contains(tolower(Name), 'it''s') and Address/City in ('Oslo', null) and Price le 2.5e1")
+                .unwrap();
+
+        let expected = binary(
+            BinaryOp::And,
+            binary(
+                BinaryOp::And,
+                Box::new(Expr::Call {
+                    function: "contains".into(),
+                    args: vec![
+                        Expr::Call {
+                            function: "tolower".into(),
+                            args: vec![Expr::Member("Name".into())],
+                        },
+                        Expr::Literal(Literal::String("it's".into())),
+                    ],
+                }),
+                Box::new(Expr::In {
+                    expr: member("Address/City"),
+                    list: vec![
+                        Expr::Literal(Literal::String("Oslo".into())),
+                        Expr::Literal(Literal::Null),
+                    ],
+                }),
+            ),
+            binary(
+                BinaryOp::Le,
+                member("Price"),
+                Box::new(Expr::Literal(Literal::Float(25.0))),
+            ),
+        );
+        assert_eq!(*expected, expr);
+
+        // keywords and function names are case-insensitive
+        assert_eq!(
+            parse_filter("not contains(a, 'x') and b in (1, true)"),
+            parse_filter("NOT Contains(a, 'x') And b IN (1, True)")
+        );
+
+        let expr = parse_filter("(a or b) and c").unwrap();
+        assert!(matches!(
+            expr,
+            Expr::Binary {
+                op: BinaryOp::And,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_error_positions() {
+        assert_eq!(error(8, "expected `)`"), parse_filter("(a eq 1 b"));
+        assert_eq!(error(5, "expected an operand"), parse_filter("a eq and"));
+        assert_eq!(
+            error(10, "unexpected end of expression"),
+            parse_filter("a eq 1 and")
+        );
+        assert_eq!(error(8, "unterminated string"), parse_filter("Name eq 'x"));
+        assert_eq!(
+            error(8, "unexpected character `#`"),
+            parse_filter("Name eq #")
+        );
+        assert_eq!(
+            error(0, "unknown function `frobnicate`"),
+            parse_filter("frobnicate(a)")
+        );
+        assert_eq!(
+            error(4, "`substring` expects 2 to 3 arguments, found 1"),
+            parse_filter("not substring(a)")
+        );
+        assert_eq!(
+            error(7, "expected an operator or the end of the expression"),
+            parse_filter("a eq 1 b")
+        );
+        assert_eq!(error(5, "invalid number `1x`"), parse_filter("a eq 1x"));
+    }
+
+    #[test]
+    fn test_date_and_guid_literals() {
+        let literal = |value: &str| match parse_filter(&format!("a eq {}", value)).unwrap() {
+            Expr::Binary { right, .. } => *right,
+            expr => panic!("unexpected expression {:?}", expr),
+        };
+
+        assert_eq!(
+            Expr::Literal(Literal::Date("2020-01-31".into())),
+            literal("2020-01-31")
+        );
+        assert_eq!(
+            Expr::Literal(Literal::DateTimeOffset("2020-01-01T00:00:00Z".into())),
+            literal("2020-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            Expr::Literal(Literal::DateTimeOffset(
+                "2020-01-01T12:30:59.123+02:00".into()
+            )),
+            literal("2020-01-01T12:30:59.123+02:00")
+        );
+        assert_eq!(
+            Expr::Literal(Literal::DateTimeOffset("2020-01-01T12:30-05:00".into())),
+            literal("2020-01-01T12:30-05:00")
+        );
+        assert_eq!(
+            Expr::Literal(Literal::Guid("01234567-89ab-cdef-0123-456789ABCDEF".into())),
+            literal("01234567-89ab-cdef-0123-456789ABCDEF")
+        );
+        assert_eq!(
+            Expr::Literal(Literal::Guid("abcdef01-2345-6789-abcd-ef0123456789".into())),
+            literal("abcdef01-2345-6789-abcd-ef0123456789")
+        );
+        assert_eq!(int(-4), Box::new(literal("-4")));
+        assert_eq!(Expr::Literal(Literal::Float(1e-5)), literal("1e-5"));
+
+        let expr = parse_filter("BirthDate gt 2020-01-01 and year(BirthDate) lt 2021").unwrap();
+        assert!(matches!(
+            expr,
+            Expr::Binary {
+                op: BinaryOp::And,
+                ..
+            }
+        ));
+
+        assert_eq!(
+            error(5, "invalid date `2020-13-01`"),
+            parse_filter("a eq 2020-13-01")
+        );
+        assert_eq!(
+            error(5, "invalid date `2020-01-01T25:00Z`"),
+            parse_filter("a eq 2020-01-01T25:00Z")
+        );
+        assert_eq!(
+            error(5, "invalid date `2020-01-01T10:00`"),
+            parse_filter("a eq 2020-01-01T10:00")
+        );
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let limits = ODataLimits { max_depth: 3 };
+        assert!(parse_filter_with_limits("not not a", &limits).is_ok());
+        assert!(parse_filter_with_limits("(a) eq length(b)", &limits).is_ok());
+        assert_eq!(
+            error(12, "expression is nested too deeply"),
+            parse_filter_with_limits("not not not a", &limits)
+        );
+        assert_eq!(
+            error(3, "expression is nested too deeply"),
+            parse_filter_with_limits("(((a)))", &limits)
+        );
+        assert_eq!(
+            error(26, "expression is nested too deeply"),
+            parse_filter_with_limits("a in (length(trim(tolower(b))))", &limits)
+        );
+
+        // untrusted input can't overflow the stack
+        let input = format!("{}a", "not ".repeat(10_000));
+        assert_eq!(
+            error(400, "expression is nested too deeply"),
+            parse_filter(&input)
+        );
+        let input = format!("{}a{}", "(".repeat(20_000), ")".repeat(20_000));
+        assert_eq!(
+            error(100, "expression is nested too deeply"),
+            parse_filter(&input)
+        );
+    }
+}
@@ -0,0 +1,537 @@
+//!
+//! The odata module reads [OData](https://docs.oasis-open.org/odata/odata/v4.01/odata-v4.01-part2-url-conventions.html)
+//! system query options from a [`QueryMap`].
+//!
+//! `$filter` and `$orderby` are parsed into [`Expr`] trees, with comparison, logical and
+//! arithmetic operators, and the common string, date and math functions.
+//! Literals are `null`, booleans, numbers, strings, dates, date time offsets and GUIDs,
+//! other literals like durations are rejected.
+//! Errors report the option and the byte offset where parsing failed.
+//! Nesting is limited by [`ODataLimits`], so untrusted input can't exhaust the stack.
+//!
+//! ```
+//! use query_map::{odata::{BinaryOp, Expr, ODataQuery}, QueryMap};
+//!
+//! let map: QueryMap = vec![
+//!     ("$filter".to_string(), "Price lt 10 and contains(Name, 'milk')".to_string()),
+//!     ("$top".to_string(), "5".to_string()),
+//! ]
+//! .into_iter()
+//! .collect();
+//!
+//! let query = ODataQuery::parse(&map).unwrap();
+//! assert_eq!(Some(5), query.top);
+//! assert!(matches!(query.filter, Some(Expr::Binary { op: BinaryOp::And, .. })));
+//! ```
+//!
+
+use std::{error::Error, fmt};
+
+use crate::{jsonapi::SortDirection, QueryMap};
+
+mod expr;
+use expr::{parse_expression, syntax, Parser};
+pub use expr::{parse_filter, parse_filter_with_limits, BinaryOp, Expr, Literal, UnaryOp};
+
+/// Default maximum nesting of expressions and `$expand` options
+pub const DEFAULT_MAX_DEPTH: usize = 100;
+
+/// Options accepted in parentheses after an `$expand` item
+const EXPAND_OPTIONS: &[&str] = &[
+    "$filter", "$select", "$expand", "$orderby", "$top", "$skip", "$count",
+];
+
+/// Limits applied while parsing untrusted system query options
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ODataLimits {
+    /// Maximum nesting of operands in expressions, like parentheses,
+    /// function arguments and `not`, and of options in `$expand` items
+    pub max_depth: usize,
+}
+
+impl Default for ODataLimits {
+    fn default() -> Self {
+        ODataLimits {
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+/// Error returned when system query options are not valid
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ODataError {
+    /// The option value has a syntax error
+    Syntax {
+        /// Option name, like `$filter`
+        option: String,
+        /// Byte offset of the error in the option value
+        position: usize,
+        /// Description of the error
+        message: String,
+    },
+    /// The option value is not valid, like a negative `$top`
+    InvalidValue {
+        /// Option name, like `$top`
+        option: String,
+        /// The rejected value
+        value: String,
+    },
+}
+
+impl fmt::Display for ODataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ODataError::Syntax {
+                option,
+                position,
+                message,
+            } => write!(
+                f,
+                "invalid `{}` at position {}: {}",
+                option, position, message
+            ),
+            ODataError::InvalidValue { option, value } => {
+                write!(f, "invalid `{}` value `{}`", option, value)
+            }
+        }
+    }
+}
+
+impl Error for ODataError {}
+
+/// An `$orderby` item
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderBy {
+    /// Expression to sort by, usually a property path
+    pub expr: Expr,
+    /// Sort direction, ascending by default
+    pub direction: SortDirection,
+}
+
+/// An `$expand` item, with its nested options
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpandItem {
+    /// Navigation property path
+    pub path: String,
+    /// Options in parentheses, like `Orders($select=Id;$top=5)`
+    pub options: ODataQuery,
+}
+
+/// System query options read from a [`QueryMap`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ODataQuery {
+    /// `$filter` expression
+    pub filter: Option<Expr>,
+    /// `$select` property paths
+    pub select: Option<Vec<String>>,
+    /// `$expand` navigation properties
+    pub expand: Option<Vec<ExpandItem>>,
+    /// `$orderby` items
+    pub orderby: Option<Vec<OrderBy>>,
+    /// `$top` value
+    pub top: Option<u64>,
+    /// `$skip` value
+    pub skip: Option<u64>,
+    /// `$count` value
+    pub count: Option<bool>,
+}
+
+impl ODataQuery {
+    /// Read the system query options from a [`QueryMap`] with the default [`ODataLimits`],
+    /// other params are ignored
+    pub fn parse(map: &QueryMap) -> Result<ODataQuery, ODataError> {
+        ODataQuery::parse_with_limits(map, &ODataLimits::default())
+    }
+
+    /// Read the system query options from a [`QueryMap`],
+    /// rejecting options that exceed the given limits
+    pub fn parse_with_limits(
+        map: &QueryMap,
+        limits: &ODataLimits,
+    ) -> Result<ODataQuery, ODataError> {
+        Ok(ODataQuery {
+            filter: map
+                .first("$filter")
+                .map(|value| parse_expression("$filter", value, limits))
+                .transpose()?,
+            select: map.first("$select").map(parse_select).transpose()?,
+            expand: map
+                .first("$expand")
+                .map(|value| parse_expand(value, limits))
+                .transpose()?,
+            orderby: map
+                .first("$orderby")
+                .map(|value| parse_orderby(value, limits))
+                .transpose()?,
+            top: map
+                .first("$top")
+                .map(|v| parse_number("$top", v))
+                .transpose()?,
+            skip: map
+                .first("$skip")
+                .map(|v| parse_number("$skip", v))
+                .transpose()?,
+            count: map.first("$count").map(parse_count).transpose()?,
+        })
+    }
+}
+
+fn parse_number(option: &str, value: &str) -> Result<u64, ODataError> {
+    value.parse().map_err(|_| invalid_value(option, value))
+}
+
+fn parse_count(value: &str) -> Result<bool, ODataError> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(invalid_value("$count", value)),
+    }
+}
+
+fn parse_select(value: &str) -> Result<Vec<String>, ODataError> {
+    split_top_level("$select", value, ',')?
+        .into_iter()
+        .map(|(offset, item)| {
+            let path = item.trim();
+            let valid = !path.is_empty()
+                && path
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '_' | '/' | '.' | '*' | '$' | '@'));
+            if valid {
+                Ok(path.to_string())
+            } else {
+                Err(syntax("$select", offset, "expected a property path"))
+            }
+        })
+        .collect()
+}
+
+fn parse_expand(value: &str, limits: &ODataLimits) -> Result<Vec<ExpandItem>, ODataError> {
+    split_top_level("$expand", value, ',')?
+        .into_iter()
+        .map(|(offset, item)| {
+            let (path, options) = match item.find('(') {
+                Some(open) => {
+                    // nested options use the remaining depth
+                    let limits = ODataLimits {
+                        max_depth: limits.max_depth.checked_sub(1).ok_or_else(|| {
+                            syntax("$expand", offset + open, "options are nested too deeply")
+                        })?,
+                    };
+                    let close = item
+                        .rfind(')')
+                        .filter(|close| *close > open)
+                        .ok_or_else(|| {
+                            syntax("$expand", offset + open, "unbalanced parentheses")
+                        })?;
+                    if !item[close + 1..].trim().is_empty() {
+                        return Err(syntax("$expand", offset + close + 1, "expected `,`"));
+                    }
+                    let options =
+                        parse_expand_options(&item[open + 1..close], offset + open + 1, &limits)?;
+                    (&item[..open], options)
+                }
+                None => (item, ODataQuery::default()),
+            };
+
+            let path = path.trim();
+            if path.is_empty() {
+                return Err(syntax("$expand", offset, "expected a navigation property"));
+            }
+            Ok(ExpandItem {
+                path: path.to_string(),
+                options,
+            })
+        })
+        .collect()
+}
+
+/// Parse `;` separated options nested in an `$expand` item.
+///
+/// Only the options in [`EXPAND_OPTIONS`] are accepted, once each.
+/// Errors are reported for `$expand`, with positions relative to the whole `$expand` value.
+fn parse_expand_options(
+    value: &str,
+    offset: usize,
+    limits: &ODataLimits,
+) -> Result<ODataQuery, ODataError> {
+    let segments = split_top_level("$expand", value, ';').map_err(|err| match err {
+        ODataError::Syntax {
+            option,
+            position,
+            message,
+        } => syntax(&option, offset + position, &message),
+        err => err,
+    })?;
+
+    // start of each option's value, to report absolute positions
+    let mut starts = Vec::new();
+    let mut pairs = Vec::new();
+    for (start, option) in segments {
+        let (key, value) = option
+            .split_once('=')
+            .ok_or_else(|| syntax("$expand", offset + start, "expected `option=value`"))?;
+        let key_start = offset + start + key.len() - key.trim_start().len();
+        let key = key.trim();
+        if !EXPAND_OPTIONS.contains(&key) {
+            return Err(syntax(
+                "$expand",
+                key_start,
+                &format!("unknown option `{}`", key),
+            ));
+        }
+        if starts.iter().any(|(name, _)| *name == key) {
+            return Err(syntax(
+                "$expand",
+                key_start,
+                &format!("duplicate option `{}`", key),
+            ));
+        }
+        starts.push((key, start + option.len() - value.len()));
+        pairs.push((key.to_string(), value.to_string()));
+    }
+
+    let map: QueryMap = pairs.into_iter().collect();
+    ODataQuery::parse_with_limits(&map, limits).map_err(|err| match err {
+        ODataError::Syntax {
+            option,
+            position,
+            message,
+        } => {
+            let start = starts
+                .iter()
+                .find(|(name, _)| *name == option)
+                .map(|(_, start)| *start)
+                .unwrap_or_default();
+            ODataError::Syntax {
+                option: "$expand".to_string(),
+                position: offset + start + position,
+                message: format!("in `{}`: {}", option, message),
+            }
+        }
+        err => err,
+    })
+}
+
+fn parse_orderby(value: &str, limits: &ODataLimits) -> Result<Vec<OrderBy>, ODataError> {
+    let mut parser = Parser::new("$orderby", value, limits)?;
+    let mut items = Vec::new();
+    loop {
+        let expr = parser.expression(0)?;
+        let direction = match parser.word() {
+            None => SortDirection::Ascending,
+            Some((_, word)) if word.eq_ignore_ascii_case("asc") => SortDirection::Ascending,
+            Some((_, word)) if word.eq_ignore_ascii_case("desc") => SortDirection::Descending,
+            Some((position, _)) => return Err(parser.error(position, "expected `asc` or `desc`")),
+        };
+        items.push(OrderBy { expr, direction });
+        if !parser.separator()? {
+            return Ok(items);
+        }
+    }
+}
+
+/// Split a value on `separator`, ignoring separators in parentheses and quoted strings.
+///
+/// Returns every segment with its byte offset in `value`,
+/// or a syntax error for `option` at a `)` without a matching `(`.
+fn split_top_level<'a>(
+    option: &str,
+    value: &'a str,
+    separator: char,
+) -> Result<Vec<(usize, &'a str)>, ODataError> {
+    let mut segments = Vec::new();
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut start = 0;
+    for (idx, c) in value.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| syntax(option, idx, "unbalanced parentheses"))?;
+            }
+            c if c == separator && !quoted && depth == 0 => {
+                segments.push((start, &value[start..idx]));
+                start = idx + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    segments.push((start, &value[start..]));
+    Ok(segments)
+}
+
+fn invalid_value(option: &str, value: &str) -> ODataError {
+    ODataError::InvalidValue {
+        option: option.to_string(),
+        value: value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_map as map;
+
+    #[test]
+    fn test_parse_options() {
+        let query = ODataQuery::parse(&map(&[
+            ("$select", "Name, Address/City,*"),
+            ("$orderby", "Price desc, length(Name)"),
+            ("$top", "10"),
+            ("$skip", "20"),
+            ("$count", "true"),
+            ("page", "2"),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            Some(vec!["Name".into(), "Address/City".into(), "*".into()]),
+            query.select
+        );
+        assert_eq!(
+            Some(vec![
+                OrderBy {
+                    expr: Expr::Member("Price".into()),
+                    direction: SortDirection::Descending
+                },
+                OrderBy {
+                    expr: Expr::Call {
+                        function: "length".into(),
+                        args: vec![Expr::Member("Name".into())]
+                    },
+                    direction: SortDirection::Ascending
+                },
+            ]),
+            query.orderby
+        );
+        assert_eq!(Some(10), query.top);
+        assert_eq!(Some(20), query.skip);
+        assert_eq!(Some(true), query.count);
+        assert_eq!(None, query.filter);
+        assert_eq!(None, query.expand);
+
+        assert_eq!(Ok(ODataQuery::default()), ODataQuery::parse(&map(&[])));
+    }
+
+    #[test]
+    fn test_parse_expand() {
+        let query = ODataQuery::parse(&map(&[(
+            "$expand",
+            "Orders($select=Id,Total;$filter=Total gt 10;$top=5), Customer",
+        )]))
+        .unwrap();
+
+        let expand = query.expand.unwrap();
+        assert_eq!(2, expand.len());
+        assert_eq!("Orders", expand[0].path);
+        assert_eq!(
+            Some(vec!["Id".into(), "Total".into()]),
+            expand[0].options.select
+        );
+        assert_eq!(Some(5), expand[0].options.top);
+        assert!(expand[0].options.filter.is_some());
+        assert_eq!("Customer", expand[1].path);
+        assert_eq!(ODataQuery::default(), expand[1].options);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Err(ODataError::InvalidValue {
+                option: "$top".into(),
+                value: "-1".into()
+            }),
+            ODataQuery::parse(&map(&[("$top", "-1")]))
+        );
+        assert_eq!(
+            Err(ODataError::InvalidValue {
+                option: "$count".into(),
+                value: "yes".into()
+            }),
+            ODataQuery::parse(&map(&[("$count", "yes")]))
+        );
+        assert_eq!(
+            Err(syntax("$select", 5, "expected a property path")),
+            ODataQuery::parse(&map(&[("$select", "Name,,Id")]))
+        );
+        assert_eq!(
+            Err(syntax("$orderby", 5, "expected `asc` or `desc`")),
+            ODataQuery::parse(&map(&[("$orderby", "Name up")]))
+        );
+        assert_eq!(
+            Err(syntax(
+                "$filter",
+                15,
+                "expected an operator or the end of the expression"
+            )),
+            ODataQuery::parse(&map(&[("$filter", "Name eq 'milk' Price")]))
+        );
+
+        assert_eq!(
+            Err(syntax("$select", 1, "unbalanced parentheses")),
+            ODataQuery::parse(&map(&[("$select", "a),b")]))
+        );
+        assert_eq!(
+            Err(syntax("$expand", 14, "unbalanced parentheses")),
+            ODataQuery::parse(&map(&[("$expand", "Orders($top=1))")]))
+        );
+        assert_eq!(
+            Err(syntax("$expand", 19, "unbalanced parentheses")),
+            ODataQuery::parse(&map(&[("$expand", "Orders($select=a),b)")]))
+        );
+
+        let err = ODataQuery::parse(&map(&[("$expand", "Orders($filter=Total gt)")])).unwrap_err();
+        assert_eq!(
+            syntax("$expand", 23, "in `$filter`: unexpected end of expression"),
+            err
+        );
+        assert_eq!(
+            "invalid `$expand` at position 23: in `$filter`: unexpected end of expression",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_expand_options_errors() {
+        assert_eq!(
+            Err(syntax("$expand", 14, "duplicate option `$top`")),
+            ODataQuery::parse(&map(&[("$expand", "Orders($top=1;$top=5)")]))
+        );
+        assert_eq!(
+            Err(syntax("$expand", 7, "unknown option `$levels`")),
+            ODataQuery::parse(&map(&[("$expand", "Orders($levels=2)")]))
+        );
+        assert_eq!(
+            Err(syntax("$expand", 15, "unknown option `$fliter`")),
+            ODataQuery::parse(&map(&[("$expand", "Orders($top=1; $fliter=Id eq 1)")]))
+        );
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let limits = ODataLimits { max_depth: 1 };
+        let data = map(&[("$expand", "A($expand=B)")]);
+        assert!(ODataQuery::parse_with_limits(&data, &limits).is_ok());
+
+        let data = map(&[("$expand", "A($expand=B($expand=C))")]);
+        assert_eq!(
+            Err(syntax(
+                "$expand",
+                11,
+                "in `$expand`: options are nested too deeply"
+            )),
+            ODataQuery::parse_with_limits(&data, &limits)
+        );
+
+        // untrusted input can't overflow the stack
+        let value = format!("{}A{}", "A($expand=".repeat(5_000), ")".repeat(5_000));
+        assert!(ODataQuery::parse(&map(&[("$expand", &value)])).is_err());
+        let value = format!("{}a{}", "(".repeat(20_000), ")".repeat(20_000));
+        assert!(ODataQuery::parse(&map(&[("$orderby", &value)])).is_err());
+    }
+}
@@ -0,0 +1,506 @@
+//!
+//! The pagination module reads pagination params from a [`QueryMap`] and rewrites them
+//! to build links to other pages.
+//!
+//! Three styles are supported: `page`/`per_page`, `offset`/`limit` and opaque `cursor`s.
+//! The style is detected from the params in the map, and [`PaginationConfig::default_style`]
+//! is used when the map doesn't include any of them.
+//! Building links requires the feature `url-query`.
+//!
+//! ```
+//! use query_map::{pagination::{Pagination, PaginationConfig}, QueryMap};
+//!
+//! let config = PaginationConfig::default().max_limit(50);
+//! let map: QueryMap = vec![("page".to_string(), "2".to_string()), ("per_page".to_string(), "100".to_string())]
+//!     .into_iter()
+//!     .collect();
+//!
+//! let pagination = config.parse(&map).unwrap();
+//! assert_eq!(Pagination::Page { page: 2, per_page: 50 }, pagination);
+//! ```
+//!
+
+use std::{error::Error, fmt, sync::Arc};
+
+use crate::QueryMap;
+
+/// Default number of items per page
+pub const DEFAULT_LIMIT: u64 = 20;
+
+/// Default maximum number of items per page
+pub const DEFAULT_MAX_LIMIT: u64 = 100;
+
+/// Pagination params read from a [`QueryMap`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pagination {
+    /// Page based pagination, pages start at 1
+    Page {
+        /// Page number
+        page: u64,
+        /// Number of items per page
+        per_page: u64,
+    },
+    /// Offset based pagination
+    Offset {
+        /// Number of items to skip
+        offset: u64,
+        /// Number of items to return
+        limit: u64,
+    },
+    /// Cursor based pagination
+    Cursor {
+        /// Opaque cursor, `None` for the first page
+        cursor: Option<String>,
+        /// Number of items to return
+        limit: u64,
+    },
+}
+
+impl Pagination {
+    /// Return the number of items requested
+    #[must_use]
+    pub fn limit(&self) -> u64 {
+        match self {
+            Pagination::Page { per_page, .. } => *per_page,
+            Pagination::Offset { limit, .. } | Pagination::Cursor { limit, .. } => *limit,
+        }
+    }
+
+    /// Return the params for the next page.
+    ///
+    /// Cursors are opaque, use [`Pagination::with_cursor`] with the cursor returned by your store.
+    #[must_use]
+    pub fn next(&self) -> Option<Pagination> {
+        match self {
+            Pagination::Page { page, per_page } => Some(Pagination::Page {
+                page: page.checked_add(1)?,
+                per_page: *per_page,
+            }),
+            Pagination::Offset { offset, limit } => Some(Pagination::Offset {
+                offset: offset.checked_add(*limit)?,
+                limit: *limit,
+            }),
+            Pagination::Cursor { .. } => None,
+        }
+    }
+
+    /// Return the params for the previous page, if this is not the first page
+    #[must_use]
+    pub fn prev(&self) -> Option<Pagination> {
+        match self {
+            Pagination::Page { page, per_page } if *page > 1 => Some(Pagination::Page {
+                page: page - 1,
+                per_page: *per_page,
+            }),
+            Pagination::Offset { offset, limit } if *offset > 0 => Some(Pagination::Offset {
+                offset: offset.saturating_sub(*limit),
+                limit: *limit,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Return cursor params that point to `cursor`, keeping the limit
+    #[must_use]
+    pub fn with_cursor(&self, cursor: impl Into<String>) -> Pagination {
+        Pagination::Cursor {
+            cursor: Some(cursor.into()),
+            limit: self.limit(),
+        }
+    }
+}
+
+/// Pagination style used when a map doesn't include pagination params
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PaginationStyle {
+    /// `page` and `per_page` params
+    #[default]
+    Page,
+    /// `offset` and `limit` params
+    Offset,
+    /// `cursor` and `limit` params
+    Cursor,
+}
+
+/// Error returned when pagination params are not valid
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PaginationError {
+    /// The param is not a valid number
+    InvalidNumber {
+        /// Param name
+        param: String,
+        /// The rejected value
+        value: String,
+    },
+    /// The param must be greater than zero
+    Zero {
+        /// Param name
+        param: String,
+    },
+}
+
+impl fmt::Display for PaginationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaginationError::InvalidNumber { param, value } => {
+                write!(f, "`{}` value `{}` is not a valid number", param, value)
+            }
+            PaginationError::Zero { param } => write!(f, "`{}` must be greater than zero", param),
+        }
+    }
+}
+
+impl Error for PaginationError {}
+
+/// Param names, defaults and limits used to read [`Pagination`] params
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaginationConfig {
+    page_param: String,
+    per_page_param: String,
+    offset_param: String,
+    limit_param: String,
+    cursor_param: String,
+    default_limit: u64,
+    max_limit: u64,
+    default_style: PaginationStyle,
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        PaginationConfig {
+            page_param: "page".into(),
+            per_page_param: "per_page".into(),
+            offset_param: "offset".into(),
+            limit_param: "limit".into(),
+            cursor_param: "cursor".into(),
+            default_limit: DEFAULT_LIMIT,
+            max_limit: DEFAULT_MAX_LIMIT,
+            default_style: PaginationStyle::default(),
+        }
+    }
+}
+
+impl PaginationConfig {
+    /// Set the name of the page param
+    #[must_use]
+    pub fn page_param(mut self, name: impl Into<String>) -> Self {
+        self.page_param = name.into();
+        self
+    }
+
+    /// Set the name of the per page param
+    #[must_use]
+    pub fn per_page_param(mut self, name: impl Into<String>) -> Self {
+        self.per_page_param = name.into();
+        self
+    }
+
+    /// Set the name of the offset param
+    #[must_use]
+    pub fn offset_param(mut self, name: impl Into<String>) -> Self {
+        self.offset_param = name.into();
+        self
+    }
+
+    /// Set the name of the limit param, used with offsets and cursors
+    #[must_use]
+    pub fn limit_param(mut self, name: impl Into<String>) -> Self {
+        self.limit_param = name.into();
+        self
+    }
+
+    /// Set the name of the cursor param
+    #[must_use]
+    pub fn cursor_param(mut self, name: impl Into<String>) -> Self {
+        self.cursor_param = name.into();
+        self
+    }
+
+    /// Set the number of items requested when the map doesn't include a limit
+    #[must_use]
+    pub fn default_limit(mut self, limit: u64) -> Self {
+        self.default_limit = limit;
+        self
+    }
+
+    /// Set the maximum number of items, larger limits are capped to this value
+    #[must_use]
+    pub fn max_limit(mut self, limit: u64) -> Self {
+        self.max_limit = limit;
+        self
+    }
+
+    /// Set the style used when the map doesn't include pagination params
+    #[must_use]
+    pub fn default_style(mut self, style: PaginationStyle) -> Self {
+        self.default_style = style;
+        self
+    }
+
+    /// Read the pagination params from a [`QueryMap`]
+    pub fn parse(&self, map: &QueryMap) -> Result<Pagination, PaginationError> {
+        let style = if map.first(&self.cursor_param).is_some() {
+            PaginationStyle::Cursor
+        } else if map.first(&self.offset_param).is_some() {
+            PaginationStyle::Offset
+        } else if map.first(&self.page_param).is_some() {
+            PaginationStyle::Page
+        } else {
+            self.default_style
+        };
+
+        let pagination = match style {
+            PaginationStyle::Page => Pagination::Page {
+                page: self.number(map, &self.page_param)?.unwrap_or(1),
+                per_page: self.limit(map, &self.per_page_param)?,
+            },
+            PaginationStyle::Offset => Pagination::Offset {
+                offset: self.number(map, &self.offset_param)?.unwrap_or(0),
+                limit: self.limit(map, &self.limit_param)?,
+            },
+            PaginationStyle::Cursor => Pagination::Cursor {
+                cursor: map
+                    .first(&self.cursor_param)
+                    .filter(|cursor| !cursor.is_empty())
+                    .map(String::from),
+                limit: self.limit(map, &self.limit_param)?,
+            },
+        };
+
+        if let Pagination::Page { page: 0, .. } = pagination {
+            return Err(PaginationError::Zero {
+                param: self.page_param.clone(),
+            });
+        }
+        Ok(pagination)
+    }
+
+    /// Return a copy of `map` where the pagination params are replaced by `pagination`
+    #[must_use]
+    pub fn apply(&self, map: &QueryMap, pagination: &Pagination) -> QueryMap {
+        let mut data = (*map.0).clone();
+        for param in [
+            &self.page_param,
+            &self.per_page_param,
+            &self.offset_param,
+            &self.limit_param,
+            &self.cursor_param,
+        ] {
+            data.remove(param);
+        }
+
+        let mut set = |param: &String, value: String| {
+            data.insert(param.clone(), vec![value]);
+        };
+        match pagination {
+            Pagination::Page { page, per_page } => {
+                set(&self.page_param, page.to_string());
+                set(&self.per_page_param, per_page.to_string());
+            }
+            Pagination::Offset { offset, limit } => {
+                set(&self.offset_param, offset.to_string());
+                set(&self.limit_param, limit.to_string());
+            }
+            Pagination::Cursor { cursor, limit } => {
+                if let Some(cursor) = cursor {
+                    set(&self.cursor_param, cursor.clone());
+                }
+                set(&self.limit_param, limit.to_string());
+            }
+        }
+        QueryMap(Arc::new(data))
+    }
+
+    /// Build the query string for `pagination`, keeping the other params in `map`
+    #[cfg(feature = "url-query")]
+    #[must_use]
+    pub fn link(&self, map: &QueryMap, pagination: &Pagination) -> String {
+        self.apply(map, pagination).to_query_string()
+    }
+
+    /// Build the query string for the page after `pagination`
+    #[cfg(feature = "url-query")]
+    #[must_use]
+    pub fn next_link(&self, map: &QueryMap, pagination: &Pagination) -> Option<String> {
+        pagination.next().map(|next| self.link(map, &next))
+    }
+
+    /// Build the query string for the page before `pagination`
+    #[cfg(feature = "url-query")]
+    #[must_use]
+    pub fn prev_link(&self, map: &QueryMap, pagination: &Pagination) -> Option<String> {
+        pagination.prev().map(|prev| self.link(map, &prev))
+    }
+
+    fn number(&self, map: &QueryMap, param: &str) -> Result<Option<u64>, PaginationError> {
+        map.first(param)
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| PaginationError::InvalidNumber {
+                        param: param.to_string(),
+                        value: value.to_string(),
+                    })
+            })
+            .transpose()
+    }
+
+    fn limit(&self, map: &QueryMap, param: &str) -> Result<u64, PaginationError> {
+        match self.number(map, param)? {
+            Some(0) => Err(PaginationError::Zero {
+                param: param.to_string(),
+            }),
+            Some(limit) => Ok(limit.min(self.max_limit)),
+            None => Ok(self.default_limit.min(self.max_limit)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_map as map;
+
+    #[test]
+    fn test_parse_styles() {
+        let config = PaginationConfig::default();
+
+        assert_eq!(
+            Ok(Pagination::Page {
+                page: 1,
+                per_page: 20
+            }),
+            config.parse(&map(&[]))
+        );
+        assert_eq!(
+            Ok(Pagination::Offset {
+                offset: 40,
+                limit: 100
+            }),
+            config.parse(&map(&[("offset", "40"), ("limit", "500")]))
+        );
+        assert_eq!(
+            Ok(Pagination::Cursor {
+                cursor: Some("abc".into()),
+                limit: 20
+            }),
+            config.parse(&map(&[("cursor", "abc")]))
+        );
+
+        let config = config.default_style(PaginationStyle::Cursor);
+        assert_eq!(
+            Ok(Pagination::Cursor {
+                cursor: None,
+                limit: 20
+            }),
+            config.parse(&map(&[]))
+        );
+    }
+
+    #[test]
+    fn test_custom_names() {
+        let config = PaginationConfig::default()
+            .page_param("p")
+            .per_page_param("size")
+            .default_limit(10);
+
+        assert_eq!(
+            Ok(Pagination::Page {
+                page: 3,
+                per_page: 10
+            }),
+            config.parse(&map(&[("p", "3")]))
+        );
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let config = PaginationConfig::default();
+        assert_eq!(
+            Err(PaginationError::InvalidNumber {
+                param: "page".into(),
+                value: "two".into()
+            }),
+            config.parse(&map(&[("page", "two")]))
+        );
+        assert_eq!(
+            Err(PaginationError::Zero {
+                param: "page".into()
+            }),
+            config.parse(&map(&[("page", "0")]))
+        );
+        assert_eq!(
+            Err(PaginationError::Zero {
+                param: "limit".into()
+            }),
+            config.parse(&map(&[("offset", "0"), ("limit", "0")]))
+        );
+    }
+
+    #[test]
+    fn test_next_and_prev() {
+        let page = Pagination::Page {
+            page: 1,
+            per_page: 10,
+        };
+        assert_eq!(None, page.prev());
+        assert_eq!(
+            Some(Pagination::Page {
+                page: 2,
+                per_page: 10
+            }),
+            page.next()
+        );
+
+        let offset = Pagination::Offset {
+            offset: 5,
+            limit: 10,
+        };
+        assert_eq!(
+            Some(Pagination::Offset {
+                offset: 0,
+                limit: 10
+            }),
+            offset.prev()
+        );
+
+        let cursor = Pagination::Cursor {
+            cursor: None,
+            limit: 10,
+        };
+        assert_eq!(None, cursor.next());
+        assert_eq!(
+            Pagination::Cursor {
+                cursor: Some("next".into()),
+                limit: 10
+            },
+            cursor.with_cursor("next")
+        );
+    }
+
+    #[cfg(feature = "url-query")]
+    #[test]
+    fn test_links() {
+        let config = PaginationConfig::default();
+        let data = map(&[("q", "rust"), ("offset", "20"), ("limit", "10")]);
+        let pagination = config.parse(&data).unwrap();
+
+        let next = config.next_link(&data, &pagination).unwrap();
+        let next = next.parse::<QueryMap>().unwrap();
+        assert_eq!("rust", next.first("q").unwrap());
+        assert_eq!("30", next.first("offset").unwrap());
+        assert_eq!("10", next.first("limit").unwrap());
+
+        let prev = config.prev_link(&data, &pagination).unwrap();
+        assert_eq!(
+            "10",
+            prev.parse::<QueryMap>().unwrap().first("offset").unwrap()
+        );
+
+        let cursor = config.link(&data, &pagination.with_cursor("abc"));
+        let cursor = cursor.parse::<QueryMap>().unwrap();
+        assert_eq!("abc", cursor.first("cursor").unwrap());
+        assert_eq!(None, cursor.first("offset"));
+    }
+}
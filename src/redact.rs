@@ -0,0 +1,282 @@
+use std::fmt;
+
+use crate::QueryMap;
+
+/// Keys redacted by the default [`RedactionPolicy`]
+pub const DEFAULT_SENSITIVE_KEYS: &[&str] = &[
+    "access_token",
+    "api_key",
+    "apikey",
+    "client_secret",
+    "id_token",
+    "password",
+    "refresh_token",
+    "secret",
+    "sig",
+    "signature",
+    "token",
+    "X-Amz-Credential",
+    "X-Amz-Security-Token",
+    "X-Amz-Signature",
+    "X-Goog-Credential",
+    "X-Goog-Signature",
+];
+
+/// Text written in place of redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Decides which values are hidden when a [`QueryMap`] is printed
+///
+/// Keys are compared ignoring ASCII case. Patterns may contain `*` wildcards,
+/// like `*_token` or `x-amz-*`.
+/// The default policy redacts the keys in [`DEFAULT_SENSITIVE_KEYS`].
+///
+/// ```
+/// use query_map::{QueryMap, RedactionPolicy};
+///
+/// let map: QueryMap = vec![("session_id".to_string(), "abc".to_string())]
+///     .into_iter()
+///     .collect();
+///
+/// let policy = RedactionPolicy::default().pattern("*_id");
+/// assert_eq!("session_id=[REDACTED]", map.redacted(&policy).to_string());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RedactionPolicy {
+    keys: Vec<String>,
+    patterns: Vec<String>,
+}
+
+impl Default for RedactionPolicy {
+    fn default() -> Self {
+        RedactionPolicy {
+            keys: DEFAULT_SENSITIVE_KEYS
+                .iter()
+                .map(|k| k.to_string())
+                .collect(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+impl RedactionPolicy {
+    /// Create a policy that doesn't redact any key
+    #[must_use]
+    pub fn empty() -> Self {
+        RedactionPolicy {
+            keys: Vec::new(),
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Redact values for an additional key
+    #[must_use]
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.keys.push(key.into());
+        self
+    }
+
+    /// Redact values for every key matching a `*` wildcard pattern
+    #[must_use]
+    pub fn pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.patterns.push(pattern.into());
+        self
+    }
+
+    /// Return true if values for this key must be redacted
+    #[must_use]
+    pub fn is_sensitive(&self, key: &str) -> bool {
+        self.keys.iter().any(|k| k.eq_ignore_ascii_case(key))
+            || self
+                .patterns
+                .iter()
+                .any(|p| matches_pattern(p.as_bytes(), key.as_bytes()))
+    }
+
+    fn apply<'a>(&self, key: &str, value: &'a str) -> &'a str {
+        if self.is_sensitive(key) {
+            REDACTED
+        } else {
+            value
+        }
+    }
+}
+
+/// Match a key against a wildcard pattern, ignoring ASCII case.
+///
+/// On a mismatch, only the last `*` is retried one byte further,
+/// so matching takes at most `pattern.len() * key.len()` steps.
+fn matches_pattern(pattern: &[u8], key: &[u8]) -> bool {
+    let (mut p, mut k) = (0, 0);
+    // pattern position after the last `*`, and the key position it matched up to
+    let mut star = None;
+    while k < key.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                star = Some((p, k));
+            }
+            Some(c) if c.eq_ignore_ascii_case(&key[k]) => {
+                p += 1;
+                k += 1;
+            }
+            _ => match star {
+                Some((star_p, star_k)) => {
+                    p = star_p;
+                    k = star_k + 1;
+                    star = Some((star_p, k));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// A view of a [`QueryMap`] that hides sensitive values when it's printed
+///
+/// `Display` writes `key=value` pairs separated by `&` without percent-encoding,
+/// which is meant for logs.
+pub struct Redacted<'a> {
+    map: &'a QueryMap,
+    policy: &'a RedactionPolicy,
+}
+
+impl QueryMap {
+    /// Return a view of this map that redacts the values selected by `policy`
+    #[must_use]
+    pub fn redacted<'a>(&'a self, policy: &'a RedactionPolicy) -> Redacted<'a> {
+        Redacted { map: self, policy }
+    }
+}
+
+impl<'a> Redacted<'a> {
+    /// Convert the redacted map into a URL query string
+    #[cfg(feature = "url-query")]
+    #[must_use]
+    pub fn to_query_string(&self) -> String {
+        form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(
+                self.map
+                    .iter()
+                    .map(|(key, value)| (key, self.policy.apply(key, value))),
+            )
+            .finish()
+    }
+}
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.map.0.iter().map(|(key, values)| {
+                let values = values
+                    .iter()
+                    .map(|value| self.policy.apply(key, value))
+                    .collect::<Vec<_>>();
+                (key, values)
+            }))
+            .finish()
+    }
+}
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (key, value)) in self.map.iter().enumerate() {
+            if i > 0 {
+                f.write_str("&")?;
+            }
+            write!(f, "{}={}", key, self.policy.apply(key, value))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for QueryMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("QueryMap")
+            .field(&self.redacted(&RedactionPolicy::default()))
+            .finish()
+    }
+}
+
+impl fmt::Display for QueryMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.redacted(&RedactionPolicy::default()), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_map as map;
+
+    #[test]
+    fn test_default_policy() {
+        let policy = RedactionPolicy::default();
+        assert!(policy.is_sensitive("access_token"));
+        assert!(policy.is_sensitive("x-amz-credential"));
+        assert!(!policy.is_sensitive("page"));
+    }
+
+    #[test]
+    fn test_patterns() {
+        let policy = RedactionPolicy::empty()
+            .pattern("*_token")
+            .pattern("x-*-key");
+        assert!(policy.is_sensitive("csrf_token"));
+        assert!(policy.is_sensitive("X-Api-Key"));
+        assert!(!policy.is_sensitive("token"));
+        assert!(!policy.is_sensitive("x-key"));
+
+        let policy = RedactionPolicy::empty().pattern("a*b*").pattern("**");
+        assert!(policy.is_sensitive(""));
+        assert!(matches_pattern(b"a*b*", b"axxbyy"));
+        assert!(!matches_pattern(b"a*b", b"axxbyy"));
+        assert!(matches_pattern(b"*a*b", b"xaaxab"));
+    }
+
+    #[test]
+    fn test_pattern_backtracking_is_bounded() {
+        let key = "a".repeat(10_000);
+        let policy = RedactionPolicy::empty().pattern("*a*a*a*a*a*a*a*a*b");
+        assert!(!policy.is_sensitive(&key));
+    }
+
+    #[test]
+    fn test_debug_and_display() {
+        let data = map(&[("access_token", "secret"), ("access_token", "other")]);
+        assert_eq!(
+            r#"QueryMap({"access_token": ["[REDACTED]", "[REDACTED]"]})"#,
+            format!("{:?}", data)
+        );
+        assert_eq!(
+            "access_token=[REDACTED]&access_token=[REDACTED]",
+            data.to_string()
+        );
+
+        let data = map(&[("page", "2")]);
+        assert_eq!(r#"QueryMap({"page": ["2"]})"#, format!("{:?}", data));
+        assert_eq!("page=2", data.to_string());
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        let data = map(&[("session", "abc")]);
+        let policy = RedactionPolicy::empty().key("Session");
+        assert_eq!("session=[REDACTED]", data.redacted(&policy).to_string());
+        assert_eq!(
+            "session=abc",
+            data.redacted(&RedactionPolicy::empty()).to_string()
+        );
+    }
+
+    #[cfg(feature = "url-query")]
+    #[test]
+    fn test_to_redacted_query_string() {
+        let data = map(&[("X-Amz-Signature", "abc"), ("q", "a b")]);
+        let query = data.to_redacted_query_string();
+        assert!(query.contains("X-Amz-Signature=%5BREDACTED%5D"));
+        assert!(query.contains("q=a+b"));
+        assert!(!query.contains("abc"));
+    }
+}
@@ -0,0 +1,69 @@
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use std::borrow::Cow;
+
+use crate::QueryMap;
+
+/// The schema accepted by the `standard` deserializers:
+/// an object with a scalar or an array of scalars as values
+impl JsonSchema for QueryMap {
+    fn schema_name() -> Cow<'static, str> {
+        "QueryMap".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        concat!(module_path!(), "::QueryMap").into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "additionalProperties": {
+                "anyOf": [
+                    { "type": ["string", "number", "boolean", "null"] },
+                    {
+                        "type": "array",
+                        "items": { "type": ["string", "number", "boolean", "null"] }
+                    }
+                ]
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schemars::schema_for;
+
+    #[test]
+    fn test_query_map_schema() {
+        let schema = schema_for!(QueryMap);
+        assert_eq!("QueryMap", schema.get("title").unwrap());
+        assert_eq!("object", schema.get("type").unwrap());
+
+        let values = schema.get("additionalProperties").unwrap();
+        assert_eq!("array", values["anyOf"][1]["type"]);
+    }
+
+    #[test]
+    fn test_typed_struct_schema() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Request {
+            query: QueryMap,
+            headers: Option<QueryMap>,
+        }
+
+        let schema = schema_for!(Request);
+        let json = serde_json::to_value(&schema).unwrap();
+        assert_eq!(
+            "#/$defs/QueryMap",
+            json["properties"]["query"]["$ref"].as_str().unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(schema_for!(QueryMap)).unwrap()["additionalProperties"],
+            json["$defs"]["QueryMap"]["additionalProperties"]
+        );
+        assert_eq!(serde_json::json!(["query"]), json["required"]);
+    }
+}
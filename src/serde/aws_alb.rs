@@ -0,0 +1,210 @@
+use percent_encoding::percent_decode_str;
+use serde_crate::{ser::SerializeMap, Deserialize, Deserializer, Serializer};
+
+use crate::{serde::standard, QueryMap};
+use std::{collections::HashMap, sync::Arc};
+
+/// Decode a value as it is encoded in a URL query string
+fn decode(value: &str) -> String {
+    percent_decode_str(&value.replace('+', " "))
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+fn encode(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+/// Decode keys and values, merging the values of keys that decode
+/// to the same string, like `a%20b` and `a+b`
+fn decode_map(value: QueryMap) -> QueryMap {
+    let mut inner: HashMap<String, Vec<String>> = HashMap::new();
+    for (k, values) in value.0.iter() {
+        inner
+            .entry(decode(k))
+            .or_default()
+            .extend(values.iter().map(|v| decode(v)));
+    }
+    QueryMap(Arc::new(inner))
+}
+
+/// Deserialize percent-encoded values into a [`QueryMap`].
+/// This function assumes that all values have been initialized.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    QueryMap::deserialize(deserializer).map(decode_map)
+}
+
+/// Deserialize `null` values into optional values
+pub fn deserialize_optional<'de, D>(deserializer: D) -> Result<Option<QueryMap>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    standard::deserialize_optional(deserializer).map(|value| value.map(decode_map))
+}
+
+/// Deserialize `null` values into default [`QueryMap`] objects
+pub fn deserialize_empty<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    standard::deserialize_empty(deserializer).map(decode_map)
+}
+
+/// Serializes [`QueryMap`] into percent-encoded single values.
+/// Like the load balancer, only the last value of each key is kept.
+pub fn serialize_query_string_parameters<S>(
+    value: &QueryMap,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = serializer.serialize_map(Some(value.0.len()))?;
+    for (k, values) in value.0.iter() {
+        if let Some(v) = values.last() {
+            map.serialize_entry(&encode(k), &encode(v))?;
+        }
+    }
+    map.end()
+}
+
+/// Serializes [`QueryMap`] into percent-encoded multi values
+pub fn serialize_multi_value_query_string_parameters<S>(
+    value: &QueryMap,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = serializer.serialize_map(Some(value.0.len()))?;
+    for (k, values) in value.0.iter() {
+        let values = values.iter().map(|v| encode(v)).collect::<Vec<_>>();
+        map.serialize_entry(&encode(k), &values)?;
+    }
+    map.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg_attr(
+        feature = "serde",
+        derive(Deserialize, Serialize),
+        serde(crate = "serde_crate")
+    )]
+    struct Event {
+        #[serde(rename = "queryStringParameters")]
+        #[serde(default, deserialize_with = "deserialize_empty")]
+        #[serde(serialize_with = "serialize_query_string_parameters")]
+        query_string_parameters: QueryMap,
+        #[serde(rename = "multiValueQueryStringParameters")]
+        #[serde(default, deserialize_with = "deserialize_optional")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        multi_value_query_string_parameters: Option<QueryMap>,
+    }
+
+    #[test]
+    fn test_deserialize_encoded_single_values() {
+        let data = serde_json::json!({
+            "queryStringParameters": {
+                "my%20key": "a%2Bb+c%26d",
+                "plain": "value"
+            }
+        });
+
+        let decoded: Event = serde_json::from_value(data).unwrap();
+        let query = decoded.query_string_parameters;
+        assert_eq!("a+b c&d", query.first("my key").unwrap());
+        assert_eq!("value", query.first("plain").unwrap());
+        assert_eq!(None, decoded.multi_value_query_string_parameters);
+
+        let encoded = serde_json::to_value(Event {
+            query_string_parameters: query,
+            multi_value_query_string_parameters: None,
+        })
+        .unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "queryStringParameters": {
+                    "my+key": "a%2Bb+c%26d",
+                    "plain": "value"
+                }
+            }),
+            encoded
+        );
+    }
+
+    #[test]
+    fn test_deserialize_encoded_multi_values() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            #[serde(deserialize_with = "deserialize")]
+            #[serde(serialize_with = "serialize_multi_value_query_string_parameters")]
+            data: QueryMap,
+        }
+
+        let data = serde_json::json!({
+            "data": {
+                "q": ["hello%2C%20world", "%E2%9C%93"]
+            }
+        });
+
+        let decoded: Test = serde_json::from_value(data).unwrap();
+        assert_eq!(vec!["hello, world", "✓"], decoded.data.all("q").unwrap());
+
+        let encoded = serde_json::to_value(decoded).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "data": {
+                    "q": ["hello%2C+world", "%E2%9C%93"]
+                }
+            }),
+            encoded
+        );
+    }
+
+    #[test]
+    fn test_deserialize_merges_equivalent_keys() {
+        let data = serde_json::json!({
+            "multiValueQueryStringParameters": {
+                "a%20b": ["1"],
+                "a+b": ["2", "3"]
+            }
+        });
+
+        let decoded: Event = serde_json::from_value(data).unwrap();
+        let query = decoded.multi_value_query_string_parameters.unwrap();
+        let mut values = query.all("a b").unwrap();
+        values.sort_unstable();
+        assert_eq!(vec!["1", "2", "3"], values);
+        assert_eq!(1, query.keys().count());
+    }
+
+    #[test]
+    fn test_serialize_keeps_last_value() {
+        let data = serde_json::json!({
+            "multiValueQueryStringParameters": {
+                "key": ["first", "last"]
+            }
+        });
+
+        let decoded: Event = serde_json::from_value(data).unwrap();
+        let encoded = serde_json::to_value(Event {
+            query_string_parameters: decoded.multi_value_query_string_parameters.unwrap(),
+            multi_value_query_string_parameters: None,
+        })
+        .unwrap();
+        assert_eq!(
+            serde_json::json!({ "queryStringParameters": { "key": "last" } }),
+            encoded
+        );
+    }
+}
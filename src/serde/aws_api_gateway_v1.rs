@@ -1,7 +1,20 @@
-use serde_crate::{ser::SerializeMap, Serializer};
+use serde_crate::{
+    de::{IgnoredAny, MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserializer, Serializer,
+};
 
-use crate::QueryMap;
-use std::collections::HashMap;
+use crate::{
+    serde::{
+        format::{serialize_with_format, ValueFormat},
+        standard::deserialize_empty,
+    },
+    QueryMap,
+};
+use std::{fmt, sync::Arc};
+
+const QUERY_STRING_PARAMETERS: &str = "queryStringParameters";
+const MULTI_VALUE_QUERY_STRING_PARAMETERS: &str = "multiValueQueryStringParameters";
 
 /// Serializes [`QueryMap`], converting value from [`Vec<String>`] to [`String`]
 pub fn serialize_query_string_parameters<S>(
@@ -11,15 +24,98 @@ pub fn serialize_query_string_parameters<S>(
 where
     S: Serializer,
 {
-    let query_string_parameters: HashMap<String, String> = (*value)
-        .iter()
-        .map(|(k, _)| (String::from(k), String::from((*value).first(k).unwrap())))
-        .collect::<HashMap<String, String>>();
-
-    let mut map = serializer.serialize_map(Some(query_string_parameters.len()))?;
-    for (k, v) in &query_string_parameters {
-        map.serialize_entry(k, v)?;
+    serialize_with_format(value, ValueFormat::First, serializer)
+}
+
+#[cfg_attr(feature = "serde", derive(Deserialize), serde(crate = "serde_crate"))]
+struct Parameters(#[serde(deserialize_with = "deserialize_empty")] QueryMap);
+
+struct MergedQueryMapVisitor;
+
+impl<'de> Visitor<'de> for MergedQueryMapVisitor {
+    type Value = QueryMap;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "an event with {} and {}",
+            QUERY_STRING_PARAMETERS, MULTI_VALUE_QUERY_STRING_PARAMETERS
+        )
     }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut single = QueryMap::default();
+        let mut multi = QueryMap::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                QUERY_STRING_PARAMETERS => single = map.next_value::<Parameters>()?.0,
+                MULTI_VALUE_QUERY_STRING_PARAMETERS => multi = map.next_value::<Parameters>()?.0,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        // multi value data is complete, single value data only keeps the last value
+        let mut inner = (*multi.0).clone();
+        for (key, values) in single.0.iter() {
+            inner.entry(key.clone()).or_insert_with(|| values.clone());
+        }
+        Ok(QueryMap(Arc::new(inner)))
+    }
+}
+
+/// Deserialize the `queryStringParameters` and `multiValueQueryStringParameters`
+/// fields of an event into a single [`QueryMap`], preferring multi value data.
+///
+/// Use it on a `#[serde(flatten)]` field, since it reads both fields from the event:
+///
+/// ```ignore
+/// #[derive(Deserialize, Serialize)]
+/// struct Event {
+///     #[serde(flatten)]
+///     #[serde(deserialize_with = "deserialize_merged_query_string_parameters")]
+///     #[serde(serialize_with = "serialize_merged_query_string_parameters")]
+///     query: QueryMap,
+/// }
+/// ```
+pub fn deserialize_merged_query_string_parameters<'de, D>(
+    deserializer: D,
+) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_map(MergedQueryMapVisitor)
+}
+
+/// Serializes [`QueryMap`] into both the `queryStringParameters` and
+/// `multiValueQueryStringParameters` fields of an event.
+///
+/// Like Api Gateway, `queryStringParameters` keeps the last value of each key,
+/// and both fields are `null` when the map is empty.
+/// Use it on a `#[serde(flatten)]` field.
+pub fn serialize_merged_query_string_parameters<S>(
+    value: &QueryMap,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let (single, multi) = if value.is_empty() {
+        (None, None)
+    } else {
+        (
+            Some(value.with_format(ValueFormat::Last)),
+            Some(value.with_format(ValueFormat::Array)),
+        )
+    };
+
+    let mut map = serializer.serialize_map(Some(2))?;
+    map.serialize_entry(QUERY_STRING_PARAMETERS, &single)?;
+    map.serialize_entry(MULTI_VALUE_QUERY_STRING_PARAMETERS, &multi)?;
     map.end()
 }
 
@@ -55,4 +151,80 @@ mod tests {
         let encoded = serde_json::to_string(&decoded).unwrap();
         assert_eq!(encoded, r#"{"v":{"key1":"value1"}}"#.to_string());
     }
+
+    #[cfg_attr(
+        feature = "serde",
+        derive(Deserialize, Serialize),
+        serde(crate = "serde_crate")
+    )]
+    struct Event {
+        #[serde(rename = "httpMethod")]
+        http_method: String,
+        #[serde(flatten)]
+        #[serde(deserialize_with = "deserialize_merged_query_string_parameters")]
+        #[serde(serialize_with = "serialize_merged_query_string_parameters")]
+        query: QueryMap,
+    }
+
+    #[test]
+    fn test_deserialize_merged_query_string_parameters() {
+        let data = serde_json::json!({
+            "httpMethod": "GET",
+            "queryStringParameters": {
+                "key1": "value2",
+                "key2": "single"
+            },
+            "multiValueQueryStringParameters": {
+                "key1": ["value1", "value2"]
+            }
+        });
+
+        let decoded: Event = serde_json::from_value(data).unwrap();
+        assert_eq!("GET", decoded.http_method);
+        assert_eq!(vec!["value1", "value2"], decoded.query.all("key1").unwrap());
+        assert_eq!(vec!["single"], decoded.query.all("key2").unwrap());
+    }
+
+    #[test]
+    fn test_deserialize_merged_null_query_string_parameters() {
+        let data = serde_json::json!({
+            "httpMethod": "GET",
+            "queryStringParameters": null,
+            "multiValueQueryStringParameters": null
+        });
+
+        let decoded: Event = serde_json::from_value(data).unwrap();
+        assert!(decoded.query.is_empty());
+
+        let decoded: Event =
+            serde_json::from_value(serde_json::json!({ "httpMethod": "GET" })).unwrap();
+        assert!(decoded.query.is_empty());
+    }
+
+    #[test]
+    fn test_merged_query_string_parameters_round_trip() {
+        let data = serde_json::json!({
+            "httpMethod": "GET",
+            "queryStringParameters": {
+                "key1": "value2",
+                "key2": "single"
+            },
+            "multiValueQueryStringParameters": {
+                "key1": ["value1", "value2"],
+                "key2": ["single"]
+            }
+        });
+
+        let decoded: Event = serde_json::from_value(data.clone()).unwrap();
+        assert_eq!(data, serde_json::to_value(decoded).unwrap());
+
+        let data = serde_json::json!({
+            "httpMethod": "GET",
+            "queryStringParameters": null,
+            "multiValueQueryStringParameters": null
+        });
+
+        let decoded: Event = serde_json::from_value(data.clone()).unwrap();
+        assert_eq!(data, serde_json::to_value(decoded).unwrap());
+    }
 }
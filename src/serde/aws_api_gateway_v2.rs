@@ -1,20 +1,26 @@
 use serde_crate::{
     de::{Error as DeError, MapAccess, Visitor},
-    ser::SerializeMap,
+    ser::{Error as SerError, SerializeSeq},
     Deserialize, Deserializer, Serializer,
 };
 
-use crate::QueryMap;
+use crate::{
+    cookie::{format_cookie_pair, parse_cookie_pair},
+    serde::{
+        format::{serialize_with_format, ValueFormat},
+        NullPolicy, OneOrMany,
+    },
+    QueryMap,
+};
 use std::{collections::HashMap, fmt, sync::Arc};
 
-#[cfg_attr(feature = "serde", derive(Deserialize), serde(crate = "serde_crate"))]
-#[serde(untagged)]
-enum OneOrMany {
-    One(String),
-    Many(Vec<String>),
-}
+#[cfg(feature = "url-query")]
+use serde_crate::{de::IgnoredAny, ser::SerializeMap};
 
-struct QueryMapVisitor;
+#[derive(Default)]
+struct QueryMapVisitor {
+    nulls: NullPolicy,
+}
 
 impl<'de> Visitor<'de> for QueryMapVisitor {
     type Value = QueryMap;
@@ -52,16 +58,16 @@ impl<'de> Visitor<'de> for QueryMapVisitor {
             .size_hint()
             .map(HashMap::with_capacity)
             .unwrap_or_else(HashMap::new);
-        // values may either be a single String or Vec<String>
+        // values may either be a single value or a sequence of values
         // to handle both single and multi value data
         while let Some((key, value)) = map.next_entry::<_, OneOrMany>()? {
-            inner.insert(
-                key,
-                match value {
-                    OneOrMany::One(one) => one.split(',').map(String::from).collect::<Vec<_>>(),
-                    OneOrMany::Many(many) => many,
-                },
-            );
+            let values = match value {
+                OneOrMany::One(Some(one)) => one.split(',').map(String::from).collect::<Vec<_>>(),
+                value => value.into_values(self.nulls),
+            };
+            if !values.is_empty() {
+                inner.insert(key, values);
+            }
         }
         Ok(QueryMap(Arc::new(inner)))
     }
@@ -73,7 +79,7 @@ pub fn deserialize<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
 where
     D: Deserializer<'de>,
 {
-    deserializer.deserialize_map(QueryMapVisitor)
+    deserializer.deserialize_map(QueryMapVisitor::default())
 }
 
 /// Deserialize `null` values into optional values
@@ -89,10 +95,12 @@ pub fn deserialize_empty<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
 where
     D: Deserializer<'de>,
 {
-    deserializer.deserialize_option(QueryMapVisitor)
+    deserializer.deserialize_option(QueryMapVisitor::default())
 }
 
-/// Serializes [`QueryMap`], converting value from [`Vec<String>`] to [`String`]
+/// Serializes [`QueryMap`], converting value from [`Vec<String>`] to [`String`].
+/// Values are joined with commas, so values that contain a comma are rejected
+/// because they could not be deserialized back.
 pub fn serialize_query_string_parameters<S>(
     value: &QueryMap,
     serializer: S,
@@ -100,18 +108,150 @@ pub fn serialize_query_string_parameters<S>(
 where
     S: Serializer,
 {
-    let query_string_parameters: HashMap<String, String> = (*value)
-        .iter()
-        .map(|(k, _)| (String::from(k), (*value).all(k).unwrap().join(",")))
-        .collect::<HashMap<String, String>>();
+    serialize_with_format(value, ValueFormat::Joined(","), serializer)
+}
+
+/// Deserialize values into a [`QueryMap`], converting `null` values into empty strings
+pub fn deserialize_nulls_as_empty<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(QueryMapVisitor {
+        nulls: NullPolicy::Empty,
+    })
+}
+
+/// Generate the JSON Schema of Api Gateway V2 query string parameters:
+/// an object with comma-joined strings as values.
+/// Use it with `#[schemars(schema_with = "...")]`.
+/// You need to enable the feature `schemars` to access this function.
+#[cfg(feature = "schemars")]
+pub fn json_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+    schemars::json_schema!({
+        "type": "object",
+        "additionalProperties": {
+            "type": "string",
+            "description": "Comma-joined values"
+        }
+    })
+}
+
+#[cfg(feature = "url-query")]
+const RAW_QUERY_STRING: &str = "rawQueryString";
+#[cfg(feature = "url-query")]
+const QUERY_STRING_PARAMETERS: &str = "queryStringParameters";
+
+#[cfg(feature = "url-query")]
+#[cfg_attr(feature = "serde", derive(Deserialize), serde(crate = "serde_crate"))]
+struct Parameters(#[serde(deserialize_with = "deserialize_empty")] QueryMap);
+
+#[cfg(feature = "url-query")]
+struct RawQueryMapVisitor;
 
-    let mut map = serializer.serialize_map(Some(query_string_parameters.len()))?;
-    for (k, v) in &query_string_parameters {
-        map.serialize_entry(k, v)?;
+#[cfg(feature = "url-query")]
+impl<'de> Visitor<'de> for RawQueryMapVisitor {
+    type Value = QueryMap;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "an event with {} and {}",
+            RAW_QUERY_STRING, QUERY_STRING_PARAMETERS
+        )
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut raw = None;
+        let mut parameters = QueryMap::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                RAW_QUERY_STRING => raw = map.next_value::<Option<String>>()?,
+                QUERY_STRING_PARAMETERS => parameters = map.next_value::<Parameters>()?.0,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        // the raw query string keeps values with commas intact
+        match raw.filter(|raw| !raw.is_empty()) {
+            Some(raw) => Ok(raw.parse().unwrap_or_default()),
+            None => Ok(parameters),
+        }
+    }
+}
+
+/// Deserialize the `rawQueryString` and `queryStringParameters` fields of an event into a [`QueryMap`].
+///
+/// Values are read from `rawQueryString` when it's present, so values that contain commas are kept intact.
+/// Otherwise, `queryStringParameters` values are split on commas.
+/// Use it on a `#[serde(flatten)]` field, since it reads both fields from the event.
+/// You need to enable the feature `url-query` to access this deserializer.
+#[cfg(feature = "url-query")]
+pub fn deserialize_with_raw_query_string<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_map(RawQueryMapVisitor)
+}
+
+/// Serializes [`QueryMap`] into both the `rawQueryString` and `queryStringParameters` fields of an event.
+///
+/// Like Api Gateway, `queryStringParameters` joins values with commas,
+/// and it's omitted when the map is empty.
+/// Use it on a `#[serde(flatten)]` field.
+/// You need to enable the feature `url-query` to access this serializer.
+#[cfg(feature = "url-query")]
+pub fn serialize_with_raw_query_string<S>(
+    value: &QueryMap,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry(RAW_QUERY_STRING, &value.to_query_string())?;
+    if !value.is_empty() {
+        let query_string_parameters = value
+            .0
+            .iter()
+            .map(|(k, values)| (k, values.join(",")))
+            .collect::<HashMap<_, _>>();
+        map.serialize_entry(QUERY_STRING_PARAMETERS, &query_string_parameters)?;
     }
     map.end()
 }
 
+/// Deserialize the `cookies` array into a [`QueryMap`].
+/// Each element is a single `name=value` cookie, and `null` values produce an empty [`QueryMap`].
+pub fn deserialize_cookies<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let cookies = Option::<Vec<String>>::deserialize(deserializer)?.unwrap_or_default();
+    Ok(cookies
+        .iter()
+        .filter_map(|cookie| parse_cookie_pair(cookie))
+        .collect())
+}
+
+/// Serializes [`QueryMap`] into a `cookies` array of `name=value` elements.
+/// Invalid cookie names and values are rejected, see [`QueryMap::to_cookie_header`].
+pub fn serialize_cookies<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut seq = serializer.serialize_seq(None)?;
+    for (name, value) in value.iter() {
+        let cookie = format_cookie_pair(name, value).map_err(S::Error::custom)?;
+        seq.serialize_element(&cookie)?;
+    }
+    seq.end()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -277,4 +417,178 @@ mod tests {
             r#"{"v":{"key1":"value1,value2,value3"}}"#.to_string()
         );
     }
+
+    #[test]
+    fn test_cookies() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            #[serde(default, deserialize_with = "deserialize_cookies")]
+            #[serde(serialize_with = "serialize_cookies")]
+            cookies: QueryMap,
+        }
+
+        let json = serde_json::json!({
+            "cookies": ["session=abc", "theme=\"dark\"", "session=def"]
+        });
+
+        let test: Test = serde_json::from_value(json).unwrap();
+        assert_eq!(vec!["abc", "def"], test.cookies.all("session").unwrap());
+        assert_eq!("dark", test.cookies.first("theme").unwrap());
+
+        let test: Test = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(test.cookies.is_empty());
+
+        let test: Test = serde_json::from_value(serde_json::json!({
+            "cookies": ["a=1", "a=2"]
+        }))
+        .unwrap();
+        let reparsed = serde_json::to_value(test).unwrap();
+        assert_eq!(serde_json::json!({ "cookies": ["a=1", "a=2"] }), reparsed);
+
+        let test = Test {
+            cookies: vec![("session".to_string(), "x; admin=1".to_string())]
+                .into_iter()
+                .collect(),
+        };
+        let err = serde_json::to_value(test).unwrap_err();
+        assert_eq!(
+            "invalid value `x; admin=1` for cookie `session`",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_serialize_rejects_values_with_commas() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            #[serde(serialize_with = "serialize_query_string_parameters")]
+            pub v: QueryMap,
+        }
+
+        let mut data = HashMap::new();
+        data.insert("q".to_string(), vec!["hello,world".to_string()]);
+        let test = Test {
+            v: QueryMap::from(data),
+        };
+
+        let err = serde_json::to_string(&test).unwrap_err();
+        assert!(err.to_string().contains("`q` contains the delimiter `,`"));
+    }
+
+    #[cfg(feature = "url-query")]
+    #[cfg_attr(
+        feature = "serde",
+        derive(Deserialize, Serialize),
+        serde(crate = "serde_crate")
+    )]
+    struct Event {
+        #[serde(rename = "rawPath")]
+        raw_path: String,
+        #[serde(flatten)]
+        #[serde(deserialize_with = "deserialize_with_raw_query_string")]
+        #[serde(serialize_with = "serialize_with_raw_query_string")]
+        query: QueryMap,
+    }
+
+    #[cfg(feature = "url-query")]
+    #[test]
+    fn test_deserialize_with_raw_query_string() {
+        let data = serde_json::json!({
+            "rawPath": "/search",
+            "rawQueryString": "q=hello%2Cworld&tag=a&tag=b",
+            "queryStringParameters": {
+                "q": "hello,world",
+                "tag": "a,b"
+            }
+        });
+
+        let event: Event = serde_json::from_value(data).unwrap();
+        assert_eq!("/search", event.raw_path);
+        assert_eq!(vec!["hello,world"], event.query.all("q").unwrap());
+        assert_eq!(vec!["a", "b"], event.query.all("tag").unwrap());
+
+        let encoded = serde_json::to_value(&event).unwrap();
+        let decoded: Event = serde_json::from_value(encoded.clone()).unwrap();
+        assert_eq!(event.query, decoded.query);
+        assert_eq!("hello,world", encoded["queryStringParameters"]["q"]);
+    }
+
+    #[cfg(feature = "url-query")]
+    #[test]
+    fn test_deserialize_without_raw_query_string() {
+        let data = serde_json::json!({
+            "rawPath": "/search",
+            "rawQueryString": "",
+            "queryStringParameters": {
+                "tag": "a,b"
+            }
+        });
+
+        let event: Event = serde_json::from_value(data).unwrap();
+        assert_eq!(vec!["a", "b"], event.query.all("tag").unwrap());
+
+        let event: Event = serde_json::from_value(serde_json::json!({ "rawPath": "/" })).unwrap();
+        assert!(event.query.is_empty());
+
+        let encoded = serde_json::to_value(&event).unwrap();
+        assert_eq!(
+            serde_json::json!({ "rawPath": "/", "rawQueryString": "" }),
+            encoded
+        );
+    }
+
+    #[test]
+    fn test_deserialize_scalars() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            #[serde(deserialize_with = "deserialize")]
+            data: QueryMap,
+            #[serde(deserialize_with = "deserialize_nulls_as_empty")]
+            empty: QueryMap,
+        }
+
+        let json = serde_json::json!({
+            "data": { "page": 2, "debug": false, "x": null },
+            "empty": { "x": null, "y": [null, 1] }
+        });
+
+        let test: Test = serde_json::from_value(json).unwrap();
+        assert_eq!("2", test.data.first("page").unwrap());
+        assert_eq!("false", test.data.first("debug").unwrap());
+        assert_eq!(None, test.data.first("x"));
+        assert_eq!(vec![""], test.empty.all("x").unwrap());
+        assert_eq!(vec!["", "1"], test.empty.all("y").unwrap());
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_json_schema() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Event {
+            #[serde(rename = "queryStringParameters")]
+            #[schemars(schema_with = "json_schema")]
+            query_string_parameters: QueryMap,
+            #[schemars(schema_with = "json_schema")]
+            headers: QueryMap,
+        }
+
+        let schema = serde_json::to_value(schemars::schema_for!(Event)).unwrap();
+        let query = &schema["properties"]["queryStringParameters"];
+        assert_eq!("object", query["type"]);
+        assert_eq!("string", query["additionalProperties"]["type"]);
+        assert_eq!(query, &schema["properties"]["headers"]);
+    }
 }
@@ -0,0 +1,94 @@
+use serde_crate::{Deserialize, Deserializer, Serializer};
+
+use crate::QueryMap;
+
+/// Deserialize a raw query string into a [`QueryMap`]
+pub fn deserialize<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let query = String::deserialize(deserializer)?;
+    Ok(query.parse().unwrap_or_default())
+}
+
+/// Deserialize `null` values into default [`QueryMap`] objects
+pub fn deserialize_empty<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let query = Option::<String>::deserialize(deserializer)?;
+    Ok(query
+        .and_then(|query| query.parse().ok())
+        .unwrap_or_default())
+}
+
+/// Serializes [`QueryMap`] into a raw query string
+pub fn serialize_query_string<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&value.to_query_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg_attr(
+        feature = "serde",
+        derive(Deserialize, Serialize),
+        serde(crate = "serde_crate")
+    )]
+    struct Request {
+        uri: String,
+        #[serde(default, deserialize_with = "deserialize_empty")]
+        #[serde(serialize_with = "serialize_query_string")]
+        querystring: QueryMap,
+    }
+
+    #[test]
+    fn test_lambda_edge_request() {
+        let data = serde_json::json!({
+            "Records": [{
+                "cf": {
+                    "request": {
+                        "clientIp": "203.0.113.178",
+                        "method": "GET",
+                        "uri": "/picture.jpg",
+                        "querystring": "size=LARGE&tag=a%20b&tag=c"
+                    }
+                }
+            }]
+        });
+
+        let request: Request =
+            serde_json::from_value(data["Records"][0]["cf"]["request"].clone()).unwrap();
+        assert_eq!("/picture.jpg", request.uri);
+        assert_eq!("LARGE", request.querystring.first("size").unwrap());
+        assert_eq!(vec!["a b", "c"], request.querystring.all("tag").unwrap());
+    }
+
+    #[test]
+    fn test_rewrite_querystring() {
+        let data = serde_json::json!({
+            "uri": "/picture.jpg",
+            "querystring": ""
+        });
+
+        let mut request: Request = serde_json::from_value(data).unwrap();
+        assert!(request.querystring.is_empty());
+
+        request.querystring = "size=small&name=a b".parse().unwrap();
+        let encoded = serde_json::to_value(request).unwrap();
+        let querystring = encoded["querystring"].as_str().unwrap();
+        assert!(querystring.contains("size=small"));
+        assert!(querystring.contains("name=a+b"));
+    }
+
+    #[test]
+    fn test_deserialize_missing_querystring() {
+        let request: Request =
+            serde_json::from_value(serde_json::json!({ "uri": "/", "querystring": null })).unwrap();
+        assert!(request.querystring.is_empty());
+    }
+}
@@ -0,0 +1,58 @@
+pub use crate::serde::aws_api_gateway_v2::{
+    deserialize, deserialize_cookies, deserialize_empty, deserialize_optional, serialize_cookies,
+    serialize_query_string_parameters,
+};
+
+#[cfg(feature = "url-query")]
+pub use crate::serde::aws_api_gateway_v2::{
+    deserialize_with_raw_query_string, serialize_with_raw_query_string,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::QueryMap;
+
+    #[test]
+    fn test_function_url_event() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Event {
+            #[serde(rename = "rawQueryString")]
+            raw_query_string: String,
+            #[serde(default, deserialize_with = "deserialize_cookies")]
+            #[serde(serialize_with = "serialize_cookies")]
+            cookies: QueryMap,
+            #[serde(rename = "queryStringParameters")]
+            #[serde(default, deserialize_with = "deserialize_empty")]
+            #[serde(serialize_with = "serialize_query_string_parameters")]
+            query_string_parameters: QueryMap,
+        }
+
+        let data = serde_json::json!({
+            "version": "2.0",
+            "routeKey": "$default",
+            "rawPath": "/my/path",
+            "rawQueryString": "parameter1=value1&parameter1=value2&parameter2=value",
+            "cookies": ["cookie1", "cookie2=value2"],
+            "queryStringParameters": {
+                "parameter1": "value1,value2",
+                "parameter2": "value"
+            },
+            "isBase64Encoded": false
+        });
+
+        let event: Event = serde_json::from_value(data).unwrap();
+        let query = &event.query_string_parameters;
+        assert_eq!(vec!["value1", "value2"], query.all("parameter1").unwrap());
+        assert_eq!("value", query.first("parameter2").unwrap());
+        assert_eq!("value2", event.cookies.first("cookie2").unwrap());
+        assert_eq!(
+            "parameter1=value1&parameter1=value2&parameter2=value",
+            event.raw_query_string
+        );
+    }
+}
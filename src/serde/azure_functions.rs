@@ -0,0 +1,103 @@
+use serde_crate::{Deserialize, Deserializer, Serializer};
+
+use crate::{
+    serde::format::{serialize_with_format, ValueFormat},
+    QueryMap,
+};
+
+pub use crate::serde::standard::{deserialize_empty, deserialize_optional};
+
+/// Deserialize the single value `Query` object into a [`QueryMap`].
+/// This function assumes that all values have been initialized.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    QueryMap::deserialize(deserializer)
+}
+
+/// Serializes [`QueryMap`] into a single value `Query` object, keeping the first value of each key
+pub fn serialize_query<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_with_format(value, ValueFormat::First, serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg_attr(
+        feature = "serde",
+        derive(Deserialize, Serialize),
+        serde(crate = "serde_crate")
+    )]
+    struct Payload {
+        #[serde(rename = "Data")]
+        data: Data,
+    }
+
+    #[cfg_attr(
+        feature = "serde",
+        derive(Deserialize, Serialize),
+        serde(crate = "serde_crate")
+    )]
+    struct Data {
+        req: HttpRequest,
+    }
+
+    #[cfg_attr(
+        feature = "serde",
+        derive(Deserialize, Serialize),
+        serde(crate = "serde_crate")
+    )]
+    struct HttpRequest {
+        #[serde(rename = "Method")]
+        method: String,
+        #[serde(rename = "Query")]
+        #[serde(default, deserialize_with = "deserialize_empty")]
+        #[serde(serialize_with = "serialize_query")]
+        query: QueryMap,
+    }
+
+    #[test]
+    fn test_http_trigger_payload() {
+        let payload: Payload =
+            serde_json::from_str(include_str!("fixtures/azure_functions_http_request.json"))
+                .unwrap();
+        let req = payload.data.req;
+
+        assert_eq!("GET", req.method);
+        assert_eq!("World", req.query.first("name").unwrap());
+        assert_eq!(vec!["a"], req.query.all("tag").unwrap());
+
+        let encoded = serde_json::to_value(req).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "Method": "GET",
+                "Query": { "name": "World", "tag": "a" }
+            }),
+            encoded
+        );
+    }
+
+    #[test]
+    fn test_serialize_keeps_first_value() {
+        let req: HttpRequest = serde_json::from_value(serde_json::json!({
+            "Method": "GET",
+            "Query": { "tag": ["a", "b"] }
+        }))
+        .unwrap();
+
+        let encoded = serde_json::to_value(req).unwrap();
+        assert_eq!(serde_json::json!({ "tag": "a" }), encoded["Query"]);
+    }
+
+    #[test]
+    fn test_missing_query() {
+        let req: HttpRequest =
+            serde_json::from_value(serde_json::json!({ "Method": "GET", "Query": null })).unwrap();
+        assert!(req.query.is_empty());
+    }
+}
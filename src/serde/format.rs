@@ -0,0 +1,307 @@
+use serde_crate::{
+    ser::{Error as SerError, SerializeMap},
+    Serialize, Serializer,
+};
+
+use crate::QueryMap;
+
+/// How the values of each key are written when serializing a [`QueryMap`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ValueFormat<'a> {
+    /// Always write an array of values, like the default [`Serialize`] implementation
+    #[default]
+    Array,
+    /// Write a single value when there is only one, and an array otherwise
+    ScalarIfSingle,
+    /// Write only the first value
+    First,
+    /// Write only the last value
+    Last,
+    /// Join all the values with a delimiter.
+    /// Values that contain the delimiter, or that form it when joined,
+    /// are rejected because they could not be split back.
+    /// The delimiter can't be empty.
+    Joined(&'a str),
+}
+
+/// A [`QueryMap`] that serializes its values with a specific [`ValueFormat`].
+///
+/// ```
+/// use query_map::{serde::format::ValueFormat, QueryMap};
+/// use std::collections::HashMap;
+///
+/// let mut data = HashMap::new();
+/// data.insert("foo".to_string(), vec!["bar".to_string(), "baz".to_string()]);
+/// let map = QueryMap::from(data);
+///
+/// let json = serde_json::to_string(&map.with_format(ValueFormat::Joined(";"))).unwrap();
+/// assert_eq!(r#"{"foo":"bar;baz"}"#, json);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct WithFormat<'a> {
+    map: &'a QueryMap,
+    format: ValueFormat<'a>,
+}
+
+impl QueryMap {
+    /// Return a wrapper that serializes this map's values with the given format
+    #[must_use]
+    pub fn with_format<'a>(&'a self, format: ValueFormat<'a>) -> WithFormat<'a> {
+        WithFormat { map: self, format }
+    }
+}
+
+impl Serialize for WithFormat<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_with_format(self.map, self.format, serializer)
+    }
+}
+
+/// Serializes [`QueryMap`], writing the values of each key with the given format
+pub fn serialize_with_format<S>(
+    value: &QueryMap,
+    format: ValueFormat<'_>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = serializer.serialize_map(Some(value.0.len()))?;
+    for (k, values) in value.0.iter() {
+        match format {
+            ValueFormat::Array => map.serialize_entry(k, values)?,
+            ValueFormat::ScalarIfSingle if values.len() == 1 => {
+                map.serialize_entry(k, &values[0])?
+            }
+            ValueFormat::ScalarIfSingle => map.serialize_entry(k, values)?,
+            ValueFormat::First => {
+                if let Some(v) = values.first() {
+                    map.serialize_entry(k, v)?;
+                }
+            }
+            ValueFormat::Last => {
+                if let Some(v) = values.last() {
+                    map.serialize_entry(k, v)?;
+                }
+            }
+            ValueFormat::Joined("") => {
+                return Err(S::Error::custom(
+                    "the delimiter to join values cannot be empty",
+                ));
+            }
+            ValueFormat::Joined(delimiter) => {
+                if values.iter().any(|v| v.contains(delimiter)) {
+                    return Err(S::Error::custom(format!(
+                        "value for `{}` contains the delimiter `{}` and cannot be joined with other values",
+                        k, delimiter
+                    )));
+                }
+                let joined = values.join(delimiter);
+                // multi-character delimiters can also appear across two joined values
+                if joined.split(delimiter).count() != values.len() {
+                    return Err(S::Error::custom(format!(
+                        "values for `{}` cannot be joined with `{}` and split back",
+                        k, delimiter
+                    )));
+                }
+                map.serialize_entry(k, &joined)?;
+            }
+        }
+    }
+    map.end()
+}
+
+/// Serializes [`QueryMap`], writing arrays of values
+pub fn serialize_array<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_with_format(value, ValueFormat::Array, serializer)
+}
+
+/// Serializes [`QueryMap`], writing single values as scalars and multiple values as arrays
+pub fn serialize_scalar_if_single<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_with_format(value, ValueFormat::ScalarIfSingle, serializer)
+}
+
+/// Serializes [`QueryMap`], writing only the first value of each key
+pub fn serialize_first<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_with_format(value, ValueFormat::First, serializer)
+}
+
+/// Serializes [`QueryMap`], writing only the last value of each key
+pub fn serialize_last<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_with_format(value, ValueFormat::Last, serializer)
+}
+
+/// Serializes [`QueryMap`], joining the values of each key with commas
+pub fn serialize_comma_joined<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_with_format(value, ValueFormat::Joined(","), serializer)
+}
+
+/// Serializes [`QueryMap`] into a sequence of `[key, value]` pairs,
+/// which keeps the order of repeated keys in formats without duplicate map keys
+pub fn serialize_pairs<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(value.iter())
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize), serde(crate = "serde_crate"))]
+struct NamedPair<'a> {
+    name: &'a str,
+    value: &'a str,
+}
+
+/// Serializes [`QueryMap`] into a sequence of `{"name": key, "value": value}` pairs
+pub fn serialize_named_pairs<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(value.iter().map(|(name, value)| NamedPair { name, value }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn map() -> QueryMap {
+        let mut data = HashMap::new();
+        data.insert("one".to_string(), vec!["a".to_string()]);
+        data.insert("many".to_string(), vec!["b".to_string(), "c".to_string()]);
+        QueryMap::from(data)
+    }
+
+    fn to_value(format: ValueFormat<'_>) -> serde_json::Value {
+        serde_json::to_value(map().with_format(format)).unwrap()
+    }
+
+    #[test]
+    fn test_value_formats() {
+        assert_eq!(
+            serde_json::json!({ "one": ["a"], "many": ["b", "c"] }),
+            to_value(ValueFormat::Array)
+        );
+        assert_eq!(
+            serde_json::json!({ "one": "a", "many": ["b", "c"] }),
+            to_value(ValueFormat::ScalarIfSingle)
+        );
+        assert_eq!(
+            serde_json::json!({ "one": "a", "many": "b" }),
+            to_value(ValueFormat::First)
+        );
+        assert_eq!(
+            serde_json::json!({ "one": "a", "many": "c" }),
+            to_value(ValueFormat::Last)
+        );
+        assert_eq!(
+            serde_json::json!({ "one": "a", "many": "b|c" }),
+            to_value(ValueFormat::Joined("|"))
+        );
+    }
+
+    #[test]
+    fn test_joined_rejects_values_with_delimiter() {
+        let err = serde_json::to_value(map().with_format(ValueFormat::Joined("b"))).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("`many` contains the delimiter `b`"));
+
+        let mut data = HashMap::new();
+        data.insert("k".to_string(), vec!["a;".to_string(), ";b".to_string()]);
+        let err = serde_json::to_value(QueryMap::from(data).with_format(ValueFormat::Joined(";;")))
+            .unwrap_err();
+        assert_eq!(
+            "values for `k` cannot be joined with `;;` and split back",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_joined_rejects_empty_delimiter() {
+        let err = serde_json::to_value(map().with_format(ValueFormat::Joined(""))).unwrap_err();
+        assert_eq!(
+            "the delimiter to join values cannot be empty",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_serialize_with() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            #[serde(serialize_with = "serialize_scalar_if_single")]
+            data: QueryMap,
+            #[serde(serialize_with = "serialize_last")]
+            last: QueryMap,
+        }
+
+        let test = Test {
+            data: map(),
+            last: map(),
+        };
+        assert_eq!(
+            serde_json::json!({
+                "data": { "one": "a", "many": ["b", "c"] },
+                "last": { "one": "a", "many": "c" }
+            }),
+            serde_json::to_value(test).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_serialize_pairs() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            #[serde(serialize_with = "serialize_pairs")]
+            pairs: QueryMap,
+            #[serde(serialize_with = "serialize_named_pairs")]
+            named: QueryMap,
+        }
+
+        let mut data = HashMap::new();
+        data.insert("a".to_string(), vec!["2".to_string(), "1".to_string()]);
+        let test = Test {
+            pairs: QueryMap::from(data.clone()),
+            named: QueryMap::from(data),
+        };
+
+        let json = serde_json::to_value(&test).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "pairs": [["a", "2"], ["a", "1"]],
+                "named": [{ "name": "a", "value": "2" }, { "name": "a", "value": "1" }]
+            }),
+            json
+        );
+
+        let reparsed: Test = serde_json::from_value(json).unwrap();
+        assert_eq!(vec!["2", "1"], reparsed.pairs.all("a").unwrap());
+        assert_eq!(test.named, reparsed.named);
+    }
+}
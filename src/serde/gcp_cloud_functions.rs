@@ -0,0 +1,123 @@
+use serde_crate::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::QueryMap;
+use std::{convert::Infallible, fmt, str::FromStr};
+
+/// A raw request URL, split into the part before the query string and the query itself
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RequestUrl {
+    /// The URL without its query string and fragment
+    pub path: String,
+    /// The decoded query string
+    pub query: QueryMap,
+}
+
+impl FromStr for RequestUrl {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.split('#').next().unwrap_or_default();
+        let (path, query) = s.split_once('?').unwrap_or((s, ""));
+        Ok(RequestUrl {
+            path: path.to_string(),
+            query: query.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for RequestUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.query.is_empty() {
+            write!(f, "{}", self.path)
+        } else {
+            write!(f, "{}?{}", self.path, self.query.to_query_string())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RequestUrl {
+    fn deserialize<D>(deserializer: D) -> Result<RequestUrl, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let url = String::deserialize(deserializer)?;
+        Ok(url.parse().unwrap_or_default())
+    }
+}
+
+impl Serialize for RequestUrl {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserialize the query string of a raw request URL into a [`QueryMap`].
+/// Use [`RequestUrl`] instead to keep the rest of the URL.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    RequestUrl::deserialize(deserializer).map(|url| url.query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg_attr(
+        feature = "serde",
+        derive(Deserialize, Serialize),
+        serde(crate = "serde_crate")
+    )]
+    struct HttpRequest {
+        method: String,
+        url: RequestUrl,
+    }
+
+    #[test]
+    fn test_http_trigger_payload() {
+        let req: HttpRequest = serde_json::from_str(include_str!(
+            "fixtures/gcp_cloud_functions_http_request.json"
+        ))
+        .unwrap();
+
+        assert_eq!("GET", req.method);
+        assert_eq!("/hello", req.url.path);
+        assert_eq!("World", req.url.query.first("name").unwrap());
+        assert_eq!(vec!["a", "b c"], req.url.query.all("tag").unwrap());
+
+        let encoded = serde_json::to_value(&req).unwrap();
+        let reparsed: HttpRequest = serde_json::from_value(encoded).unwrap();
+        assert_eq!(req.url, reparsed.url);
+    }
+
+    #[test]
+    fn test_deserialize_query_only() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            #[serde(deserialize_with = "deserialize")]
+            url: QueryMap,
+        }
+
+        let test: Test = serde_json::from_value(serde_json::json!({
+            "url": "https://example.com/hello?name=World#top"
+        }))
+        .unwrap();
+        assert_eq!("World", test.url.first("name").unwrap());
+    }
+
+    #[test]
+    fn test_request_url_without_query() {
+        let url: RequestUrl = "/hello".parse().unwrap();
+        assert_eq!("/hello", url.path);
+        assert!(url.query.is_empty());
+        assert_eq!("/hello", url.to_string());
+    }
+}
@@ -0,0 +1,144 @@
+use serde_crate::{
+    de::{Error as DeError, SeqAccess, Visitor},
+    Deserializer, Serializer,
+};
+
+use crate::{
+    serde::{format::serialize_named_pairs, NullPolicy, Pair},
+    QueryMap,
+};
+use std::{collections::HashMap, fmt, sync::Arc};
+
+struct HarParamsVisitor;
+
+impl<'de> Visitor<'de> for HarParamsVisitor {
+    type Value = QueryMap;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "an array of HAR name-value objects")
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: DeError,
+    {
+        Ok(QueryMap::default())
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: DeError,
+    {
+        Ok(QueryMap::default())
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut inner: HashMap<String, Vec<String>> = HashMap::new();
+        // params for uploaded files don't have a value, but they are still part of the form
+        while let Some(pair) = seq.next_element::<Pair>()? {
+            if let Some(value) = NullPolicy::Empty.apply(pair.value) {
+                inner.entry(pair.name).or_default().push(value);
+            }
+        }
+        Ok(QueryMap(Arc::new(inner)))
+    }
+}
+
+/// Deserialize a HAR `queryString` or `postData.params` array into a [`QueryMap`].
+/// `null` values produce an empty [`QueryMap`], and params without a value are read as empty strings.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(HarParamsVisitor)
+}
+
+/// Serializes [`QueryMap`] into a HAR array of `{"name", "value"}` objects
+pub fn serialize<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_named_pairs(value, serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg_attr(
+        feature = "serde",
+        derive(Deserialize, Serialize),
+        serde(crate = "serde_crate")
+    )]
+    struct Request {
+        method: String,
+        #[serde(rename = "queryString", with = "super")]
+        query_string: QueryMap,
+        #[serde(rename = "postData", default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        post_data: Option<PostData>,
+    }
+
+    #[cfg_attr(
+        feature = "serde",
+        derive(Deserialize, Serialize),
+        serde(crate = "serde_crate")
+    )]
+    struct PostData {
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        #[serde(default, with = "super")]
+        params: QueryMap,
+    }
+
+    #[test]
+    fn test_har_request() {
+        let request: Request =
+            serde_json::from_str(include_str!("fixtures/har_request.json")).unwrap();
+
+        assert_eq!("POST", request.method);
+        assert_eq!("rust", request.query_string.first("q").unwrap());
+        assert_eq!(vec!["a", "b"], request.query_string.all("tag").unwrap());
+
+        let params = request.post_data.unwrap().params;
+        assert_eq!("jane", params.first("user").unwrap());
+        assert_eq!("", params.first("avatar").unwrap());
+    }
+
+    #[test]
+    fn test_har_round_trip() {
+        let json = serde_json::json!({
+            "method": "GET",
+            "queryString": [
+                { "name": "tag", "value": "b" },
+                { "name": "tag", "value": "a" }
+            ]
+        });
+
+        let request: Request = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(json, serde_json::to_value(request).unwrap());
+    }
+
+    #[test]
+    fn test_har_null_params() {
+        let json = serde_json::json!({
+            "method": "GET",
+            "queryString": null,
+            "postData": { "mimeType": "text/plain" }
+        });
+
+        let request: Request = serde_json::from_value(json).unwrap();
+        assert!(request.query_string.is_empty());
+        assert!(request.post_data.unwrap().params.is_empty());
+    }
+}
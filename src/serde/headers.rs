@@ -0,0 +1,390 @@
+use serde_crate::{
+    de::{Error as DeError, IgnoredAny, MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserializer, Serializer,
+};
+
+use crate::{
+    serde::{format::ValueFormat, NullPolicy, OneOrMany},
+    QueryMap,
+};
+use std::{collections::HashMap, fmt, sync::Arc};
+
+const HEADERS: &str = "headers";
+const MULTI_VALUE_HEADERS: &str = "multiValueHeaders";
+
+/// Headers defined as comma separated lists, the only ones split by
+/// [`deserialize_comma_separated`]. Other headers, like `Date` or `Set-Cookie`,
+/// can contain commas in a single value.
+const LIST_HEADERS: &[&str] = &[
+    "accept",
+    "accept-charset",
+    "accept-encoding",
+    "accept-language",
+    "accept-ranges",
+    "access-control-allow-headers",
+    "access-control-allow-methods",
+    "access-control-expose-headers",
+    "access-control-request-headers",
+    "allow",
+    "cache-control",
+    "connection",
+    "content-encoding",
+    "content-language",
+    "forwarded",
+    "if-match",
+    "if-none-match",
+    "pragma",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "vary",
+    "via",
+    "x-forwarded-for",
+    "x-forwarded-host",
+    "x-forwarded-proto",
+];
+
+/// Split a list header on commas that are not in a quoted string
+fn split_list(value: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (idx, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                values.push(&value[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    values.push(&value[start..]);
+    values
+        .into_iter()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+struct HeaderMapVisitor {
+    split_commas: bool,
+}
+
+impl<'de> Visitor<'de> for HeaderMapVisitor {
+    type Value = QueryMap;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "a map of headers")
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: DeError,
+    {
+        Ok(QueryMap::default())
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: DeError,
+    {
+        Ok(QueryMap::default())
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut inner: HashMap<String, Vec<String>> = map
+            .size_hint()
+            .map_or_else(HashMap::new, HashMap::with_capacity);
+        // header names are case insensitive, so names that only differ
+        // in their case are merged into the same lowercase key
+        while let Some((key, value)) = map.next_entry::<String, OneOrMany>()? {
+            let key = key.to_ascii_lowercase();
+            let values = match value {
+                OneOrMany::One(Some(one))
+                    if self.split_commas && LIST_HEADERS.contains(&key.as_str()) =>
+                {
+                    split_list(&one)
+                }
+                value => value.into_values(NullPolicy::Skip),
+            };
+            if !values.is_empty() {
+                inner.entry(key).or_default().extend(values);
+            }
+        }
+        Ok(QueryMap(Arc::new(inner)))
+    }
+}
+
+/// Deserialize headers into a [`QueryMap`] with lowercase keys.
+/// Names that only differ in their case are merged, and lookups must use
+/// the lowercase name: `first("content-type")` finds a `Content-Type` header,
+/// but `first("Content-Type")` returns `None`.
+/// Single values are kept as they are.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_map(HeaderMapVisitor {
+        split_commas: false,
+    })
+}
+
+/// Deserialize `null` headers into default [`QueryMap`] objects
+pub fn deserialize_empty<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(HeaderMapVisitor {
+        split_commas: false,
+    })
+}
+
+/// Deserialize comma-joined headers, like the ones in Api Gateway V2 payloads,
+/// into a [`QueryMap`] with lowercase keys.
+/// Only headers defined as lists, like `Accept` or `X-Forwarded-For`, are split,
+/// and commas in quoted strings are kept. Other headers, like `Date` or `Set-Cookie`,
+/// are always a single value.
+pub fn deserialize_comma_separated<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(HeaderMapVisitor { split_commas: true })
+}
+
+#[cfg_attr(feature = "serde", derive(Deserialize), serde(crate = "serde_crate"))]
+struct Headers(#[serde(deserialize_with = "deserialize_empty")] QueryMap);
+
+struct MergedHeaderMapVisitor;
+
+impl<'de> Visitor<'de> for MergedHeaderMapVisitor {
+    type Value = QueryMap;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "an event with {} and {}",
+            HEADERS, MULTI_VALUE_HEADERS
+        )
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut single = QueryMap::default();
+        let mut multi = QueryMap::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                HEADERS => single = map.next_value::<Headers>()?.0,
+                MULTI_VALUE_HEADERS => multi = map.next_value::<Headers>()?.0,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let mut inner = (*multi.0).clone();
+        for (key, values) in single.0.iter() {
+            inner.entry(key.clone()).or_insert_with(|| values.clone());
+        }
+        Ok(QueryMap(Arc::new(inner)))
+    }
+}
+
+/// Deserialize the `headers` and `multiValueHeaders` fields of an Api Gateway V1
+/// or Application Load Balancer event into a single [`QueryMap`] with lowercase keys,
+/// preferring multi value data.
+/// Use it on a `#[serde(flatten)]` field, since it reads both fields from the event.
+pub fn deserialize_merged_headers<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_map(MergedHeaderMapVisitor)
+}
+
+/// Serializes [`QueryMap`] into both the `headers` and `multiValueHeaders` fields of an event.
+/// `headers` keeps the last value of each header, and both fields are `null` when the map is empty.
+/// Use it on a `#[serde(flatten)]` field.
+pub fn serialize_merged_headers<S>(value: &QueryMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let (single, multi) = if value.is_empty() {
+        (None, None)
+    } else {
+        (
+            Some(value.with_format(ValueFormat::Last)),
+            Some(value.with_format(ValueFormat::Array)),
+        )
+    };
+
+    let mut map = serializer.serialize_map(Some(2))?;
+    map.serialize_entry(HEADERS, &single)?;
+    map.serialize_entry(MULTI_VALUE_HEADERS, &multi)?;
+    map.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_case_insensitive_headers() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            #[serde(deserialize_with = "deserialize")]
+            headers: QueryMap,
+        }
+
+        let json = serde_json::json!({
+            "headers": {
+                "Accept": "text/html, application/json",
+                "X-Forwarded-For": ["10.0.0.1"],
+                "x-forwarded-for": ["10.0.0.2"]
+            }
+        });
+
+        let test: Test = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            "text/html, application/json",
+            test.headers.first("accept").unwrap()
+        );
+        assert_eq!(None, test.headers.first("Accept"));
+        let mut forwarded = test.headers.all("x-forwarded-for").unwrap();
+        forwarded.sort_unstable();
+        assert_eq!(vec!["10.0.0.1", "10.0.0.2"], forwarded);
+    }
+
+    #[test]
+    fn test_deserialize_comma_separated_headers() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            #[serde(default, deserialize_with = "deserialize_comma_separated")]
+            headers: QueryMap,
+        }
+
+        let json = serde_json::json!({
+            "headers": {
+                "Accept": "text/html, application/json",
+                "Set-Cookie": "id=a; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+                "Date": "Tue, 15 Nov 1994 08:12:31 GMT",
+                "If-None-Match": "\"a,b\", \"c\"",
+                "X-Custom": "one, two"
+            }
+        });
+
+        let test: Test = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            vec!["text/html", "application/json"],
+            test.headers.all("accept").unwrap()
+        );
+        assert_eq!(
+            vec!["id=a; Expires=Wed, 21 Oct 2015 07:28:00 GMT"],
+            test.headers.all("set-cookie").unwrap()
+        );
+        assert_eq!(
+            vec!["Tue, 15 Nov 1994 08:12:31 GMT"],
+            test.headers.all("date").unwrap()
+        );
+        assert_eq!(
+            vec!["\"a,b\"", "\"c\""],
+            test.headers.all("if-none-match").unwrap()
+        );
+        assert_eq!(vec!["one, two"], test.headers.all("x-custom").unwrap());
+
+        let test: Test = serde_json::from_value(serde_json::json!({ "headers": null })).unwrap();
+        assert!(test.headers.is_empty());
+    }
+
+    #[cfg_attr(
+        feature = "serde",
+        derive(Deserialize, Serialize),
+        serde(crate = "serde_crate")
+    )]
+    struct Event {
+        path: String,
+        #[serde(flatten)]
+        #[serde(deserialize_with = "deserialize_merged_headers")]
+        #[serde(serialize_with = "serialize_merged_headers")]
+        headers: QueryMap,
+    }
+
+    #[test]
+    fn test_deserialize_merged_headers() {
+        let json = serde_json::json!({
+            "path": "/",
+            "headers": {
+                "Host": "example.com",
+                "Set-Cookie": "b=2"
+            },
+            "multiValueHeaders": {
+                "Set-Cookie": ["a=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT", "b=2"]
+            }
+        });
+
+        let event: Event = serde_json::from_value(json).unwrap();
+        assert_eq!("/", event.path);
+        assert_eq!("example.com", event.headers.first("host").unwrap());
+        assert_eq!(None, event.headers.first("Host"));
+        let mut keys = event.headers.keys().collect::<Vec<_>>();
+        keys.sort_unstable();
+        assert_eq!(vec!["host", "set-cookie"], keys);
+        assert_eq!(
+            vec!["a=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT", "b=2"],
+            event.headers.all("set-cookie").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_merged_headers_round_trip() {
+        let json = serde_json::json!({
+            "path": "/",
+            "headers": {
+                "host": "example.com",
+                "set-cookie": "b=2"
+            },
+            "multiValueHeaders": {
+                "host": ["example.com"],
+                "set-cookie": ["a=1", "b=2"]
+            }
+        });
+
+        let event: Event = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(json, serde_json::to_value(event).unwrap());
+
+        let json = serde_json::json!({
+            "path": "/",
+            "headers": null,
+            "multiValueHeaders": null
+        });
+
+        let event: Event = serde_json::from_value(json.clone()).unwrap();
+        assert!(event.headers.is_empty());
+        assert_eq!(json, serde_json::to_value(event).unwrap());
+    }
+}
@@ -3,6 +3,18 @@
 //! You need to enable the feature `serde` to access these deserializers.
 //!
 
+use serde_crate::{
+    de::{Error as DeError, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::fmt;
+
+/// The aws_alb module implements deserializers that work with the expected format
+/// in the AWS Application Load Balancer payloads, where values are still percent-encoded.
+/// You need to enable the feature `url-query` to access these deserializers.
+#[cfg(feature = "url-query")]
+pub mod aws_alb;
+
 /// The aws_api_gateway_v1 module implements a deserializer that works with
 /// the expected format in the AWS Api Gateway V1 payloads.
 pub mod aws_api_gateway_v1;
@@ -12,6 +24,340 @@ pub mod aws_api_gateway_v1;
 /// See https://github.com/calavera/query-map-rs/issues/1#issuecomment-1114463009 for more detail.
 pub mod aws_api_gateway_v2;
 
+/// The aws_cloudfront module implements deserializers that work with the raw `querystring`
+/// field in the AWS CloudFront Lambda@Edge payloads.
+/// You need to enable the feature `url-query` to access these deserializers.
+#[cfg(feature = "url-query")]
+pub mod aws_cloudfront;
+
+/// The aws_lambda_function_url module implements deserializers that work with
+/// the expected format in the AWS Lambda function URL payloads, which follow the Api Gateway V2 format.
+pub mod aws_lambda_function_url;
+
+/// The azure_functions module implements deserializers that work with the single value
+/// `Query` object in the Azure Functions custom handler HTTP payloads.
+pub mod azure_functions;
+
+/// The format module implements serializers that write the values of each key
+/// with a configurable format, like arrays, single values or joined values.
+pub mod format;
+
+/// The gcp_cloud_functions module implements deserializers that work with
+/// the raw request URL in the Google Cloud Functions HTTP payloads.
+/// You need to enable the feature `url-query` to access these deserializers.
+#[cfg(feature = "url-query")]
+pub mod gcp_cloud_functions;
+
+/// The har module implements deserializers that work with the `queryString`
+/// and `postData.params` arrays in HTTP Archive (HAR) files.
+pub mod har;
+
+/// The headers module implements deserializers for multi value header maps
+/// in the AWS Api Gateway and Application Load Balancer payloads.
+/// Header names are case insensitive, so keys are always stored in lowercase,
+/// and lookups must use the lowercase name.
+pub mod headers;
+
 /// The standard module implements a deserializer that follows the URL encoding parser standard.
 /// See https://url.spec.whatwg.org/#urlencoded-parsing for more detail.
 pub mod standard;
+
+/// How `null` values are converted while deserializing a [`QueryMap`](crate::QueryMap)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NullPolicy {
+    /// Drop `null` values, and keys that end up without values
+    #[default]
+    Skip,
+    /// Convert `null` values into empty strings
+    Empty,
+}
+
+impl NullPolicy {
+    pub(crate) fn apply(self, value: Option<String>) -> Option<String> {
+        match self {
+            NullPolicy::Skip => value,
+            NullPolicy::Empty => Some(value.unwrap_or_default()),
+        }
+    }
+}
+
+/// Values may either be a single scalar or a sequence of scalars
+/// to handle both single and multi value data.
+/// Numbers and booleans are converted into their canonical strings,
+/// floats keep their fraction, so `1.0` stays `1.0` and `1e300` stays `1e300`,
+/// and `null` values are kept as `None` until a [`NullPolicy`] is applied.
+pub(crate) enum OneOrMany {
+    One(Option<String>),
+    Many(Vec<Option<String>>),
+}
+
+impl OneOrMany {
+    pub(crate) fn into_values(self, nulls: NullPolicy) -> Vec<String> {
+        match self {
+            OneOrMany::One(one) => nulls.apply(one).into_iter().collect(),
+            OneOrMany::Many(many) => many.into_iter().filter_map(|v| nulls.apply(v)).collect(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OneOrMany {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(OneOrManyVisitor)
+    }
+}
+
+struct Scalar(Option<String>);
+
+impl<'de> Deserialize<'de> for Scalar {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ScalarVisitor).map(Scalar)
+    }
+}
+
+struct ScalarVisitor;
+
+impl<'de> Visitor<'de> for ScalarVisitor {
+    type Value = Option<String>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "a string, number, boolean or null")
+    }
+
+    fn visit_str<E: DeError>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_string<E: DeError>(self, v: String) -> Result<Self::Value, E> {
+        Ok(Some(v))
+    }
+
+    fn visit_bool<E: DeError>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_i64<E: DeError>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_u64<E: DeError>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_f64<E: DeError>(self, v: f64) -> Result<Self::Value, E> {
+        // `Debug` keeps the fraction of whole numbers, `1.0` instead of `1`,
+        // and uses exponents for large values instead of every digit
+        Ok(Some(format!("{:?}", v)))
+    }
+
+    fn visit_unit<E: DeError>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_none<E: DeError>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+struct OneOrManyVisitor;
+
+impl<'de> Visitor<'de> for OneOrManyVisitor {
+    type Value = OneOrMany;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "a scalar value or a sequence of scalar values")
+    }
+
+    fn visit_str<E: DeError>(self, v: &str) -> Result<Self::Value, E> {
+        ScalarVisitor.visit_str(v).map(OneOrMany::One)
+    }
+
+    fn visit_string<E: DeError>(self, v: String) -> Result<Self::Value, E> {
+        ScalarVisitor.visit_string(v).map(OneOrMany::One)
+    }
+
+    fn visit_bool<E: DeError>(self, v: bool) -> Result<Self::Value, E> {
+        ScalarVisitor.visit_bool(v).map(OneOrMany::One)
+    }
+
+    fn visit_i64<E: DeError>(self, v: i64) -> Result<Self::Value, E> {
+        ScalarVisitor.visit_i64(v).map(OneOrMany::One)
+    }
+
+    fn visit_u64<E: DeError>(self, v: u64) -> Result<Self::Value, E> {
+        ScalarVisitor.visit_u64(v).map(OneOrMany::One)
+    }
+
+    fn visit_f64<E: DeError>(self, v: f64) -> Result<Self::Value, E> {
+        ScalarVisitor.visit_f64(v).map(OneOrMany::One)
+    }
+
+    fn visit_unit<E: DeError>(self) -> Result<Self::Value, E> {
+        Ok(OneOrMany::One(None))
+    }
+
+    fn visit_none<E: DeError>(self) -> Result<Self::Value, E> {
+        Ok(OneOrMany::One(None))
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut many = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(Scalar(value)) = seq.next_element()? {
+            many.push(value);
+        }
+        Ok(OneOrMany::Many(many))
+    }
+}
+
+/// A single key-value pair, either as a `[key, value]` sequence
+/// or as a `{"name": key, "value": value}` map, like the ones in HAR files.
+/// Other fields in the map are ignored, and a missing value is handled like `null`.
+pub(crate) struct Pair {
+    pub(crate) name: String,
+    pub(crate) value: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for Pair {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(PairVisitor)
+    }
+}
+
+struct PairVisitor;
+
+impl<'de> Visitor<'de> for PairVisitor {
+    type Value = Pair;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "a [key, value] pair or a {{\"name\", \"value\"}} object"
+        )
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let name = seq
+            .next_element::<String>()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        let Scalar(value) = seq
+            .next_element::<Scalar>()?
+            .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(A::Error::invalid_length(3, &self));
+        }
+        Ok(Pair { name, value })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut name = None;
+        let mut value = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "name" => name = Some(map.next_value::<String>()?),
+                "value" => value = map.next_value::<Scalar>()?.0,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        let name = name.ok_or_else(|| A::Error::missing_field("name"))?;
+        Ok(Pair { name, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(json: serde_json::Value, nulls: NullPolicy) -> Vec<String> {
+        serde_json::from_value::<OneOrMany>(json)
+            .unwrap()
+            .into_values(nulls)
+    }
+
+    #[test]
+    fn test_scalars_are_canonical_strings() {
+        let skip = NullPolicy::Skip;
+        assert_eq!(vec!["2"], values(serde_json::json!(2), skip));
+        assert_eq!(vec!["-2"], values(serde_json::json!(-2), skip));
+        assert_eq!(vec!["2.5"], values(serde_json::json!(2.5), skip));
+        assert_eq!(vec!["1.0"], values(serde_json::json!(1.0), skip));
+        assert_eq!(vec!["0.1"], values(serde_json::json!(0.1), skip));
+        assert_eq!(vec!["1e300"], values(serde_json::json!(1e300), skip));
+        assert_eq!(vec!["true"], values(serde_json::json!(true), skip));
+        assert_eq!(
+            vec!["a", "1", "false"],
+            values(serde_json::json!(["a", 1, false]), skip)
+        );
+    }
+
+    #[test]
+    fn test_null_policies() {
+        let json = serde_json::json!(["a", null, "b"]);
+        assert_eq!(vec!["a", "b"], values(json.clone(), NullPolicy::Skip));
+        assert_eq!(vec!["a", "", "b"], values(json, NullPolicy::Empty));
+
+        assert!(values(serde_json::Value::Null, NullPolicy::Skip).is_empty());
+        assert_eq!(vec![""], values(serde_json::Value::Null, NullPolicy::Empty));
+    }
+
+    #[test]
+    fn test_nested_values_are_rejected() {
+        assert!(serde_json::from_value::<OneOrMany>(serde_json::json!({ "a": 1 })).is_err());
+        assert!(serde_json::from_value::<OneOrMany>(serde_json::json!([["a"]])).is_err());
+    }
+
+    #[test]
+    fn test_pair_shapes() {
+        let pair: Pair = serde_json::from_value(serde_json::json!(["a", 1])).unwrap();
+        assert_eq!("a", pair.name);
+        assert_eq!(Some("1".to_string()), pair.value);
+
+        let pair: Pair = serde_json::from_value(serde_json::json!({
+            "name": "a",
+            "value": "1",
+            "comment": "ignored"
+        }))
+        .unwrap();
+        assert_eq!("a", pair.name);
+        assert_eq!(Some("1".to_string()), pair.value);
+
+        let pair: Pair = serde_json::from_value(serde_json::json!({ "name": "a" })).unwrap();
+        assert_eq!(None, pair.value);
+
+        assert!(serde_json::from_value::<Pair>(serde_json::json!(["a"])).is_err());
+        assert!(serde_json::from_value::<Pair>(serde_json::json!(["a", "1", "2"])).is_err());
+        assert!(serde_json::from_value::<Pair>(serde_json::json!({ "value": "1" })).is_err());
+    }
+}
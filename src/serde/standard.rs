@@ -1,20 +1,21 @@
 use serde_crate::{
-    de::{Error as DeError, MapAccess, Visitor},
+    de::{Error as DeError, MapAccess, SeqAccess, Unexpected, Visitor},
     Deserialize, Deserializer,
 };
 
-use crate::QueryMap;
+use crate::{
+    serde::{NullPolicy, OneOrMany, Pair},
+    QueryMap,
+};
 use std::{collections::HashMap, fmt, sync::Arc};
 
-#[cfg_attr(feature = "serde", derive(Deserialize), serde(crate = "serde_crate"))]
-#[serde(untagged)]
-enum OneOrMany {
-    One(String),
-    Many(Vec<String>),
+#[derive(Default)]
+struct QueryMapVisitor {
+    nulls: NullPolicy,
+    /// Accept `null` as an empty map, otherwise it is rejected
+    nullable: bool,
 }
 
-struct QueryMapVisitor;
-
 impl<'de> Visitor<'de> for QueryMapVisitor {
     type Value = QueryMap;
 
@@ -26,6 +27,9 @@ impl<'de> Visitor<'de> for QueryMapVisitor {
     where
         E: DeError,
     {
+        if !self.nullable {
+            return Err(E::invalid_type(Unexpected::Unit, &self));
+        }
         Ok(QueryMap::default())
     }
 
@@ -33,6 +37,9 @@ impl<'de> Visitor<'de> for QueryMapVisitor {
     where
         E: DeError,
     {
+        if !self.nullable {
+            return Err(E::invalid_type(Unexpected::Option, &self));
+        }
         Ok(QueryMap::default())
     }
 
@@ -40,7 +47,21 @@ impl<'de> Visitor<'de> for QueryMapVisitor {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_map(self)
+        deserialize_map_or_pairs(deserializer, self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut inner: HashMap<String, Vec<String>> = HashMap::new();
+        // pairs keep the order of repeated keys
+        while let Some(pair) = seq.next_element::<Pair>()? {
+            if let Some(value) = self.nulls.apply(pair.value) {
+                inner.entry(pair.name).or_default().push(value);
+            }
+        }
+        Ok(QueryMap(Arc::new(inner)))
     }
 
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
@@ -50,27 +71,43 @@ impl<'de> Visitor<'de> for QueryMapVisitor {
         let mut inner = map
             .size_hint()
             .map_or_else(HashMap::new, HashMap::with_capacity);
-        // values may either be a single String or Vec<String>
+        // values may either be a single value or a sequence of values
         // to handle both single and multi value data
         while let Some((key, value)) = map.next_entry::<_, OneOrMany>()? {
-            inner.insert(
-                key,
-                match value {
-                    OneOrMany::One(one) => vec![one],
-                    OneOrMany::Many(many) => many,
-                },
-            );
+            let values = value.into_values(self.nulls);
+            if !values.is_empty() {
+                inner.insert(key, values);
+            }
         }
         Ok(QueryMap(Arc::new(inner)))
     }
 }
 
+/// Human-readable formats can contain either a map or a sequence of pairs,
+/// other formats always contain a map.
+///
+/// Self-describing binary formats, like MessagePack or CBOR, are not human-readable,
+/// so they only accept maps. Use [`deserialize_pairs`] to read pairs from them.
+fn deserialize_map_or_pairs<'de, D>(
+    deserializer: D,
+    visitor: QueryMapVisitor,
+) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_any(visitor)
+    } else {
+        deserializer.deserialize_map(visitor)
+    }
+}
+
 impl<'de> Deserialize<'de> for QueryMap {
     fn deserialize<D>(deserializer: D) -> Result<QueryMap, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_map(QueryMapVisitor)
+        deserialize_map_or_pairs(deserializer, QueryMapVisitor::default())
     }
 }
 
@@ -87,7 +124,43 @@ pub fn deserialize_empty<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
 where
     D: Deserializer<'de>,
 {
-    deserializer.deserialize_option(QueryMapVisitor)
+    deserializer.deserialize_option(QueryMapVisitor {
+        nullable: true,
+        ..QueryMapVisitor::default()
+    })
+}
+
+/// Deserialize a sequence of `[name, value]` or `{"name": .., "value": ..}` pairs
+/// into a [`QueryMap`], in any format, including binary ones
+pub fn deserialize_pairs<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_seq(QueryMapVisitor::default())
+}
+
+/// Deserialize values into a [`QueryMap`], converting `null` values according to `nulls`.
+///
+/// Call it from your own function to use a policy with `deserialize_with`.
+pub fn deserialize_with_null_policy<'de, D>(
+    deserializer: D,
+    nulls: NullPolicy,
+) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(QueryMapVisitor {
+        nulls,
+        nullable: true,
+    })
+}
+
+/// Deserialize values into a [`QueryMap`], converting `null` values into empty strings
+pub fn deserialize_nulls_as_empty<'de, D>(deserializer: D) -> Result<QueryMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_with_null_policy(deserializer, NullPolicy::Empty)
 }
 
 #[cfg(test)]
@@ -112,6 +185,23 @@ mod tests {
         assert!(test.data.is_empty());
     }
 
+    #[test]
+    fn test_deserialize_rejects_null() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            data: QueryMap,
+        }
+
+        let json = serde_json::json!({ "data": null });
+
+        let err = serde_json::from_value::<Test>(json).err().unwrap();
+        assert_eq!("invalid type: null, expected a QueryMap", err.to_string());
+    }
+
     #[test]
     fn test_deserialize_missing() {
         #[cfg_attr(
@@ -247,4 +337,108 @@ mod tests {
         let reparsed = serde_json::to_value(test).unwrap();
         assert_eq!(json, reparsed);
     }
+
+    #[test]
+    fn test_deserialize_scalars() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            data: QueryMap,
+        }
+
+        let json = serde_json::json!({
+            "data": {
+                "page": 2,
+                "ratio": 0.5,
+                "debug": true,
+                "x": null,
+                "ids": [1, null, 3]
+            }
+        });
+
+        let test: Test = serde_json::from_value(json).unwrap();
+        assert_eq!("2", test.data.first("page").unwrap());
+        assert_eq!("0.5", test.data.first("ratio").unwrap());
+        assert_eq!("true", test.data.first("debug").unwrap());
+        assert_eq!(None, test.data.all("x"));
+        assert_eq!(vec!["1", "3"], test.data.all("ids").unwrap());
+    }
+
+    #[test]
+    fn test_deserialize_nulls_as_empty() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            #[serde(deserialize_with = "deserialize_nulls_as_empty")]
+            data: QueryMap,
+        }
+
+        let json = serde_json::json!({
+            "data": {
+                "x": null,
+                "ids": [1, null]
+            }
+        });
+
+        let test: Test = serde_json::from_value(json).unwrap();
+        assert_eq!(vec![""], test.data.all("x").unwrap());
+        assert_eq!(vec!["1", ""], test.data.all("ids").unwrap());
+
+        let test: Test = serde_json::from_value(serde_json::json!({ "data": null })).unwrap();
+        assert!(test.data.is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_pairs() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            data: QueryMap,
+            #[serde(default, deserialize_with = "deserialize_empty")]
+            named: QueryMap,
+        }
+
+        let json = serde_json::json!({
+            "data": [["a", "1"], ["a", "2"], ["b", 3], ["c", null]],
+            "named": [
+                { "name": "a", "value": "1" },
+                { "name": "a", "value": "2", "comment": "second" }
+            ]
+        });
+
+        let test: Test = serde_json::from_value(json).unwrap();
+        assert_eq!(vec!["1", "2"], test.data.all("a").unwrap());
+        assert_eq!("3", test.data.first("b").unwrap());
+        assert_eq!(None, test.data.first("c"));
+        assert_eq!(vec!["1", "2"], test.named.all("a").unwrap());
+    }
+
+    #[test]
+    fn test_deserialize_pairs_only() {
+        #[cfg_attr(
+            feature = "serde",
+            derive(Deserialize, Serialize),
+            serde(crate = "serde_crate")
+        )]
+        struct Test {
+            #[serde(deserialize_with = "deserialize_pairs")]
+            data: QueryMap,
+        }
+
+        let json = serde_json::json!({ "data": [["a", "1"], ["a", "2"]] });
+        let test: Test = serde_json::from_value(json).unwrap();
+        assert_eq!(vec!["1", "2"], test.data.all("a").unwrap());
+
+        let json = serde_json::json!({ "data": { "a": "1" } });
+        assert!(serde_json::from_value::<Test>(json).is_err());
+    }
 }
@@ -0,0 +1,301 @@
+//!
+//! The signing module appends HMAC-SHA256 signatures to a [`QueryMap`] and verifies them,
+//! which is useful to build presigned links.
+//! You need to enable the feature `signing` to access these functions.
+//!
+//! The signature is computed over [`QueryMap::to_canonical_query_string`],
+//! without the signature param itself. Expiry timestamps are written in seconds
+//! since the Unix epoch, and they are covered by the signature.
+//!
+//! ```
+//! use query_map::{signing::Signer, QueryMap};
+//! use std::time::{Duration, SystemTime};
+//!
+//! let signer = Signer::new(b"secret key");
+//! let map: QueryMap = "file=report.pdf".parse().unwrap();
+//!
+//! let expires_at = SystemTime::now() + Duration::from_secs(300);
+//! let signed = signer.sign_with_expiry(&map, expires_at);
+//! let link = format!("/download?{}", signed.to_query_string());
+//!
+//! assert!(signer.verify(&signed).is_ok());
+//! ```
+//!
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::QueryMap;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Default name of the signature param
+pub const DEFAULT_SIGNATURE_PARAM: &str = "signature";
+
+/// Default name of the expiry param
+pub const DEFAULT_EXPIRES_PARAM: &str = "expires";
+
+/// Error returned when a signed [`QueryMap`] cannot be verified
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SignatureError {
+    /// The map doesn't include a signature
+    Missing,
+    /// The signature doesn't match the data in the map
+    Invalid,
+    /// The expiry param is not a valid timestamp
+    InvalidExpiry,
+    /// The signature expired
+    Expired {
+        /// Expiry timestamp in seconds since the Unix epoch
+        expires: u64,
+    },
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::Missing => write!(f, "missing signature"),
+            SignatureError::Invalid => write!(f, "invalid signature"),
+            SignatureError::InvalidExpiry => write!(f, "invalid expiry timestamp"),
+            SignatureError::Expired { expires } => {
+                write!(f, "signature expired at timestamp {}", expires)
+            }
+        }
+    }
+}
+
+impl Error for SignatureError {}
+
+/// Signs and verifies [`QueryMap`] data with a secret key
+#[derive(Clone)]
+pub struct Signer {
+    key: Vec<u8>,
+    signature_param: String,
+    expires_param: String,
+}
+
+impl fmt::Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Signer")
+            .field("key", &crate::REDACTED)
+            .field("signature_param", &self.signature_param)
+            .field("expires_param", &self.expires_param)
+            .finish()
+    }
+}
+
+impl Signer {
+    /// Create a signer with the given secret key
+    #[must_use]
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Signer {
+            key: key.as_ref().to_vec(),
+            signature_param: DEFAULT_SIGNATURE_PARAM.to_string(),
+            expires_param: DEFAULT_EXPIRES_PARAM.to_string(),
+        }
+    }
+
+    /// Set the name of the signature param
+    #[must_use]
+    pub fn signature_param(mut self, name: impl Into<String>) -> Self {
+        self.signature_param = name.into();
+        self
+    }
+
+    /// Set the name of the expiry param
+    #[must_use]
+    pub fn expires_param(mut self, name: impl Into<String>) -> Self {
+        self.expires_param = name.into();
+        self
+    }
+
+    /// Return a copy of `map` with a signature param that never expires
+    #[must_use]
+    pub fn sign(&self, map: &QueryMap) -> QueryMap {
+        self.sign_inner(map, None)
+    }
+
+    /// Return a copy of `map` with a signature param that expires at `expires_at`
+    #[must_use]
+    pub fn sign_with_expiry(&self, map: &QueryMap, expires_at: SystemTime) -> QueryMap {
+        self.sign_inner(map, Some(unix_timestamp(expires_at)))
+    }
+
+    /// Verify the signature in `map`, and check that it didn't expire
+    pub fn verify(&self, map: &QueryMap) -> Result<(), SignatureError> {
+        self.verify_at(map, SystemTime::now())
+    }
+
+    /// Verify the signature in `map`, and check that it didn't expire at `now`
+    pub fn verify_at(&self, map: &QueryMap, now: SystemTime) -> Result<(), SignatureError> {
+        let signature = match map.all(&self.signature_param).as_deref() {
+            None | Some([]) => return Err(SignatureError::Missing),
+            Some([signature]) => decode_hex(signature).ok_or(SignatureError::Invalid)?,
+            Some(_) => return Err(SignatureError::Invalid),
+        };
+
+        let mut data = (*map.0).clone();
+        data.remove(&self.signature_param);
+        let unsigned = QueryMap(Arc::new(data));
+        // the comparison runs in constant time
+        self.mac(&unsigned)
+            .verify_slice(&signature)
+            .map_err(|_| SignatureError::Invalid)?;
+
+        if let Some(expires) = unsigned.all(&self.expires_param) {
+            let expires = match expires.as_slice() {
+                [expires] => expires
+                    .parse::<u64>()
+                    .map_err(|_| SignatureError::InvalidExpiry)?,
+                _ => return Err(SignatureError::InvalidExpiry),
+            };
+            if unix_timestamp(now) >= expires {
+                return Err(SignatureError::Expired { expires });
+            }
+        }
+        Ok(())
+    }
+
+    fn sign_inner(&self, map: &QueryMap, expires: Option<u64>) -> QueryMap {
+        let mut data: HashMap<String, Vec<String>> = (*map.0).clone();
+        data.remove(&self.signature_param);
+        data.remove(&self.expires_param);
+        if let Some(expires) = expires {
+            data.insert(self.expires_param.clone(), vec![expires.to_string()]);
+        }
+
+        let unsigned = QueryMap(Arc::new(data));
+        let signature = encode_hex(&self.mac(&unsigned).finalize().into_bytes());
+
+        let mut data = (*unsigned.0).clone();
+        data.insert(self.signature_param.clone(), vec![signature]);
+        QueryMap(Arc::new(data))
+    }
+
+    fn mac(&self, map: &QueryMap) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(map.to_canonical_query_string().as_bytes());
+        mac
+    }
+}
+
+fn unix_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let chunks = s.as_bytes().chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+    chunks
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn signed(query: &str) -> (Signer, QueryMap) {
+        let signer = Signer::new("key");
+        let map = signer.sign(&query.parse().unwrap());
+        (signer, map)
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let (signer, map) = signed("b=2&a=1&a=0");
+        assert_eq!(Ok(()), signer.verify(&map));
+        assert_eq!(64, map.first("signature").unwrap().len());
+        assert_eq!(None, map.first("expires"));
+
+        // the signature doesn't depend on the order of the keys
+        let (_, other) = signed("a=1&b=2&a=0");
+        assert_eq!(map.first("signature"), other.first("signature"));
+    }
+
+    #[test]
+    fn test_reject_reordered_values() {
+        let (signer, map) = signed("role=user&role=admin");
+        let mut data = (*map.0).clone();
+        data.insert("role".into(), vec!["admin".into(), "user".into()]);
+        assert_eq!(
+            Err(SignatureError::Invalid),
+            signer.verify(&QueryMap::from(data))
+        );
+    }
+
+    #[test]
+    fn test_reject_tampered_maps() {
+        let (signer, map) = signed("file=a.pdf");
+        let mut data = (*map.0).clone();
+        data.insert("file".into(), vec!["b.pdf".into()]);
+        assert_eq!(
+            Err(SignatureError::Invalid),
+            signer.verify(&QueryMap::from(data))
+        );
+
+        let other = Signer::new("other key");
+        assert_eq!(Err(SignatureError::Invalid), other.verify(&map));
+
+        let unsigned: QueryMap = "file=a.pdf".parse().unwrap();
+        assert_eq!(Err(SignatureError::Missing), signer.verify(&unsigned));
+
+        let garbage: QueryMap = "file=a.pdf&signature=zz".parse().unwrap();
+        assert_eq!(Err(SignatureError::Invalid), signer.verify(&garbage));
+    }
+
+    #[test]
+    fn test_expiry() {
+        let signer = Signer::new("key")
+            .signature_param("sig")
+            .expires_param("exp");
+        let expires_at = UNIX_EPOCH + Duration::from_secs(1_000);
+        let map = signer.sign_with_expiry(&"file=a.pdf".parse().unwrap(), expires_at);
+        assert_eq!("1000", map.first("exp").unwrap());
+
+        let before = UNIX_EPOCH + Duration::from_secs(999);
+        assert_eq!(Ok(()), signer.verify_at(&map, before));
+        assert_eq!(
+            Err(SignatureError::Expired { expires: 1_000 }),
+            signer.verify_at(&map, expires_at)
+        );
+
+        // extending the expiry invalidates the signature
+        let mut data = (*map.0).clone();
+        data.insert("exp".into(), vec!["2000".into()]);
+        assert_eq!(
+            Err(SignatureError::Invalid),
+            signer.verify_at(&QueryMap::from(data), before)
+        );
+    }
+
+    #[test]
+    fn test_resigning_replaces_signature() {
+        let (signer, map) = signed("a=1");
+        let resigned = signer.sign(&map);
+        assert_eq!(
+            vec![map.first("signature").unwrap()],
+            resigned.all("signature").unwrap()
+        );
+    }
+}
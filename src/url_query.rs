@@ -1,7 +1,7 @@
-use crate::QueryMap;
+use crate::{ParseError, ParseLimits, QueryMap, RedactionPolicy};
 use std::{
-    collections::hash_map::Entry::{Occupied, Vacant},
-    collections::HashMap,
+    io::{self, BufRead},
+    iter,
 };
 
 impl QueryMap {
@@ -11,33 +11,224 @@ impl QueryMap {
             .extend_pairs(self.iter())
             .finish()
     }
+
+    /// Convert a [`QueryMap`] into a URL query string with a stable order.
+    ///
+    /// Pairs are sorted by key, comparing their bytes, and the values of each key
+    /// keep their order, so two maps with the same data always produce the same string,
+    /// and maps where repeated values are in a different order don't.
+    #[must_use]
+    pub fn to_canonical_query_string(&self) -> String {
+        let mut pairs = self.iter().collect::<Vec<_>>();
+        // a stable sort keeps the order of repeated values
+        pairs.sort_by(|a, b| a.0.cmp(b.0));
+        form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(pairs)
+            .finish()
+    }
+
+    /// Convert this map into a URL query string, replacing the values
+    /// selected by the default [`RedactionPolicy`]
+    #[must_use]
+    pub fn to_redacted_query_string(&self) -> String {
+        self.redacted(&RedactionPolicy::default()).to_query_string()
+    }
+
+    /// Parse a URL query string, rejecting input that exceeds the given limits
+    pub fn parse_with_limits(s: &str, limits: &ParseLimits) -> Result<QueryMap, ParseError> {
+        let mut tokenizer = QueryTokenizer::with_limits(*limits);
+        tokenizer.feed(s.as_bytes());
+        tokenizer.finish();
+        iter::from_fn(|| tokenizer.next_pair()).collect()
+    }
 }
 
 impl std::str::FromStr for QueryMap {
     type Err = std::convert::Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let pairs = form_urlencoded::parse(s.as_bytes()).into_owned();
+        Ok(QueryMap::parse_with_limits(s, &ParseLimits::default())
+            .expect("default limits are unbounded"))
+    }
+}
 
-        let mut map: HashMap<String, Vec<String>> = HashMap::new();
-        for (k, v) in pairs {
-            match map.entry(k) {
-                Occupied(entry) => {
-                    entry.into_mut().push(v);
-                }
-                Vacant(entry) => {
-                    entry.insert(vec![v]);
+/// A pull parser that decodes `key=value` pairs from a query string fed in chunks.
+///
+/// Pairs are only decoded once their `&` separator, or the end of the input, has been seen,
+/// so chunks can be split anywhere, including in the middle of an escape sequence.
+///
+/// ```
+/// use query_map::QueryTokenizer;
+///
+/// let mut tokenizer = QueryTokenizer::new();
+/// tokenizer.feed(b"foo=b%2");
+/// assert_eq!(None, tokenizer.next_pair());
+///
+/// tokenizer.feed(b"0ar&baz=quux");
+/// tokenizer.finish();
+/// assert_eq!(Some(Ok(("foo".into(), "b ar".into()))), tokenizer.next_pair());
+/// assert_eq!(Some(Ok(("baz".into(), "quux".into()))), tokenizer.next_pair());
+/// assert_eq!(None, tokenizer.next_pair());
+/// ```
+#[derive(Debug, Default)]
+pub struct QueryTokenizer {
+    buffer: Vec<u8>,
+    pos: usize,
+    /// Number of bytes after `pos` already known not to contain `&`
+    scanned: usize,
+    count: usize,
+    finished: bool,
+    failed: bool,
+    limits: ParseLimits,
+}
+
+impl QueryTokenizer {
+    /// Create a tokenizer without limits
+    #[must_use]
+    pub fn new() -> Self {
+        QueryTokenizer::default()
+    }
+
+    /// Create a tokenizer that rejects input exceeding the given limits
+    #[must_use]
+    pub fn with_limits(limits: ParseLimits) -> Self {
+        QueryTokenizer {
+            limits,
+            ..QueryTokenizer::default()
+        }
+    }
+
+    /// Append the next chunk of input
+    pub fn feed(&mut self, chunk: &[u8]) {
+        if self.pos > 0 {
+            self.buffer.drain(..self.pos);
+            self.pos = 0;
+        }
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Mark the end of the input, so the last pair can be decoded
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// Return true once the input has been marked as finished
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Return the next decoded pair.
+    ///
+    /// `None` means that more input is needed, or that the input
+    /// has been fully consumed after calling [`QueryTokenizer::finish`].
+    /// After an error, the tokenizer stops and always returns `None`.
+    pub fn next_pair(&mut self) -> Option<Result<(String, String), ParseError>> {
+        if self.failed {
+            return None;
+        }
+        let next = self.decode_next();
+        self.failed = matches!(next, Some(Err(_)));
+        next
+    }
+
+    fn decode_next(&mut self) -> Option<Result<(String, String), ParseError>> {
+        loop {
+            let rest = &self.buffer[self.pos..];
+            // only scan the bytes fed since the last call, so long pairs
+            // fed in small chunks are not scanned again and again
+            let (pair, consumed) = match rest[self.scanned..].iter().position(|b| *b == b'&') {
+                Some(idx) => (&rest[..self.scanned + idx], self.scanned + idx + 1),
+                None if self.finished && !rest.is_empty() => (rest, rest.len()),
+                None => {
+                    self.scanned = rest.len();
+                    return match self.limits.check_pair_length(rest.len()) {
+                        Ok(()) => None,
+                        Err(err) => Some(Err(err)),
+                    };
                 }
             };
+
+            if pair.is_empty() {
+                self.pos += consumed;
+                self.scanned = 0;
+                continue;
+            }
+            if let Err(err) = self.limits.check_pair_length(pair.len()) {
+                return Some(Err(err));
+            }
+            if let Err(err) = self.limits.check_pairs(self.count + 1) {
+                return Some(Err(err));
+            }
+
+            let decoded = form_urlencoded::parse(pair)
+                .next()
+                .map(|(k, v)| (k.into_owned(), v.into_owned()));
+            self.pos += consumed;
+            self.scanned = 0;
+            self.count += 1;
+            if let Some(decoded) = decoded {
+                return Some(Ok(decoded));
+            }
+        }
+    }
+}
+
+/// An iterator over the decoded pairs of a query string read from a [`BufRead`].
+///
+/// Limit violations are reported as [`io::ErrorKind::InvalidData`] errors.
+pub struct QueryPairs<R> {
+    reader: R,
+    tokenizer: QueryTokenizer,
+}
+
+impl<R: BufRead> QueryPairs<R> {
+    /// Create an iterator over the pairs in `reader`
+    pub fn new(reader: R) -> Self {
+        QueryPairs::with_limits(reader, ParseLimits::default())
+    }
+
+    /// Create an iterator that rejects input exceeding the given limits
+    pub fn with_limits(reader: R, limits: ParseLimits) -> Self {
+        QueryPairs {
+            reader,
+            tokenizer: QueryTokenizer::with_limits(limits),
         }
+    }
+}
+
+impl<R: BufRead> Iterator for QueryPairs<R> {
+    type Item = io::Result<(String, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(pair) = self.tokenizer.next_pair() {
+                return Some(pair.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)));
+            }
+            if self.tokenizer.is_finished() || self.tokenizer.failed {
+                return None;
+            }
 
-        Ok(QueryMap(map.into()))
+            let read = match self.reader.fill_buf() {
+                Ok(buf) => {
+                    self.tokenizer.feed(buf);
+                    buf.len()
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Some(Err(err)),
+            };
+            if read == 0 {
+                self.tokenizer.finish();
+            }
+            self.reader.consume(read);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
 
     #[test]
     fn test_empty_map_to_query_string() {
@@ -59,6 +250,12 @@ mod tests {
         assert!(query.contains("baz=quux"));
     }
 
+    #[test]
+    fn test_map_to_canonical_query_string() {
+        let map: QueryMap = "b=2&a=z&a=y&c=a+b".parse().unwrap();
+        assert_eq!("a=z&a=y&b=2&c=a+b", map.to_canonical_query_string());
+    }
+
     #[test]
     fn test_map_from_str() {
         let data = "foo=bar&baz=quux&foo=qux";
@@ -82,4 +279,114 @@ mod tests {
 
         assert_eq!(data, query);
     }
+
+    #[test]
+    fn test_tokenizer_with_split_escapes() {
+        let mut tokenizer = QueryTokenizer::new();
+        let mut pairs = Vec::new();
+        for chunk in ["a%", "2", "0b=c%2", "6d&&e", "=f+g"] {
+            tokenizer.feed(chunk.as_bytes());
+            pairs.extend(iter::from_fn(|| tokenizer.next_pair()).map(Result::unwrap));
+        }
+        tokenizer.finish();
+        pairs.extend(iter::from_fn(|| tokenizer.next_pair()).map(Result::unwrap));
+
+        assert_eq!(
+            vec![
+                ("a b".to_string(), "c&d".to_string()),
+                ("e".to_string(), "f g".to_string())
+            ],
+            pairs
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_with_byte_chunks() {
+        let input = format!("a={}&b=c", "x".repeat(1000));
+        let mut tokenizer = QueryTokenizer::new();
+        let mut pairs = Vec::new();
+        for byte in input.as_bytes() {
+            tokenizer.feed(&[*byte]);
+            pairs.extend(iter::from_fn(|| tokenizer.next_pair()).map(Result::unwrap));
+        }
+        tokenizer.finish();
+        pairs.extend(iter::from_fn(|| tokenizer.next_pair()).map(Result::unwrap));
+
+        assert_eq!(
+            vec![
+                ("a".to_string(), "x".repeat(1000)),
+                ("b".to_string(), "c".to_string())
+            ],
+            pairs
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_rejects_long_pending_pair() {
+        let mut tokenizer = QueryTokenizer::with_limits(ParseLimits {
+            max_pair_length: 4,
+            ..ParseLimits::default()
+        });
+        tokenizer.feed(b"foo=");
+        assert_eq!(None, tokenizer.next_pair());
+        tokenizer.feed(b"bar");
+        assert_eq!(
+            Some(Err(ParseError::PairTooLong { limit: 4 })),
+            tokenizer.next_pair()
+        );
+
+        // the tokenizer stops after the first error
+        tokenizer.feed(b"&baz=quux");
+        tokenizer.finish();
+        assert_eq!(None, tokenizer.next_pair());
+    }
+
+    #[test]
+    fn test_query_pairs_from_buf_read() {
+        let reader = io::BufReader::with_capacity(3, "foo=b%20ar&baz=quux".as_bytes());
+        let pairs = QueryPairs::new(reader)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                ("foo".to_string(), "b ar".to_string()),
+                ("baz".to_string(), "quux".to_string())
+            ],
+            pairs
+        );
+
+        let reader = io::BufReader::new("a=1&b=2".as_bytes());
+        let limits = ParseLimits {
+            max_pairs: 1,
+            ..ParseLimits::default()
+        };
+        let err = QueryPairs::with_limits(reader, limits)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+        let reader = io::BufReader::new("a=1&b=2&c=3".as_bytes());
+        let mut pairs = QueryPairs::with_limits(reader, limits);
+        assert!(pairs.next().unwrap().is_ok());
+        assert!(pairs.next().unwrap().is_err());
+        assert!(pairs.next().is_none());
+    }
+
+    #[test]
+    fn test_parse_with_limits() {
+        let limits = ParseLimits {
+            max_pairs: 2,
+            max_pair_length: 16,
+        };
+
+        let map = QueryMap::parse_with_limits("foo=bar&&foo=qux", &limits).unwrap();
+        assert_eq!(vec!["bar", "qux"], map.all("foo").unwrap());
+
+        let err = QueryMap::parse_with_limits("a=1&b=2&c=3", &limits).unwrap_err();
+        assert_eq!(ParseError::TooManyPairs { limit: 2 }, err);
+
+        let err = QueryMap::parse_with_limits("a=0123456789abcdef", &limits).unwrap_err();
+        assert_eq!(ParseError::PairTooLong { limit: 16 }, err);
+    }
 }
@@ -0,0 +1,476 @@
+//!
+//! The validation module checks the contents of a [`QueryMap`] against a declarative [`QuerySchema`].
+//!
+//! Validation reports every violation at once, so handlers can return a single response
+//! listing all the problems with a request.
+//! Pattern rules require the feature `regex`.
+//! With the feature `actix`, a [`ValidationError`] can be returned from handlers
+//! as a `400 Bad Request` response.
+//!
+//! ```
+//! use query_map::{
+//!     validation::{Param, QuerySchema, UnknownKeys},
+//!     QueryMap,
+//! };
+//!
+//! let schema = QuerySchema::new()
+//!     .param("page", Param::new().single().range(1.0, 100.0))
+//!     .param("sort", Param::new().required().one_of(["asc", "desc"]))
+//!     .unknown_keys(UnknownKeys::Deny);
+//!
+//! let map: QueryMap = vec![("page".to_string(), "0".to_string())]
+//!     .into_iter()
+//!     .collect();
+//!
+//! let err = schema.validate(&map).unwrap_err();
+//! assert_eq!(2, err.errors.len());
+//! ```
+//!
+
+use std::{collections::HashSet, error::Error, fmt};
+
+use crate::QueryMap;
+
+/// How to report keys that are not declared in a [`QuerySchema`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnknownKeys {
+    /// Ignore unknown keys
+    Allow,
+    /// Report unknown keys as warnings
+    #[default]
+    Warn,
+    /// Report unknown keys as errors
+    Deny,
+}
+
+/// Rules for the values of a single key
+#[derive(Clone, Debug, Default)]
+pub struct Param {
+    required: bool,
+    min_values: Option<usize>,
+    max_values: Option<usize>,
+    allowed: Option<Vec<String>>,
+    range: Option<(f64, f64)>,
+    #[cfg(feature = "regex")]
+    pattern: Option<regex::Regex>,
+}
+
+impl Param {
+    /// Create a param that is optional and accepts any value
+    #[must_use]
+    pub fn new() -> Self {
+        Param::default()
+    }
+
+    /// Reject maps that don't include this key
+    #[must_use]
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Accept at most one value
+    #[must_use]
+    pub fn single(self) -> Self {
+        self.max_values(1)
+    }
+
+    /// Accept at least `min` values when the key is present
+    #[must_use]
+    pub fn min_values(mut self, min: usize) -> Self {
+        self.min_values = Some(min);
+        self
+    }
+
+    /// Accept at most `max` values
+    #[must_use]
+    pub fn max_values(mut self, max: usize) -> Self {
+        self.max_values = Some(max);
+        self
+    }
+
+    /// Accept only the given values
+    #[must_use]
+    pub fn one_of<I, S>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed = Some(values.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Accept only numbers between `min` and `max`, both included
+    #[must_use]
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.range = Some((min, max));
+        self
+    }
+
+    /// Accept only values that match a regular expression
+    #[cfg(feature = "regex")]
+    #[must_use]
+    pub fn pattern(mut self, pattern: regex::Regex) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    fn check(&self, key: &str, values: Option<Vec<&str>>, errors: &mut Vec<Violation>) {
+        let mut violation = |reason| {
+            errors.push(Violation {
+                key: key.to_string(),
+                reason,
+            })
+        };
+
+        let values = match values {
+            Some(values) => values,
+            None if self.required => return violation(Reason::Missing),
+            None => return,
+        };
+
+        let found = values.len();
+        if let Some(min) = self.min_values.filter(|min| found < *min) {
+            violation(Reason::TooFewValues { min, found });
+        }
+        if let Some(max) = self.max_values.filter(|max| found > *max) {
+            violation(Reason::TooManyValues { max, found });
+        }
+
+        for value in values {
+            if let Some(allowed) = &self.allowed {
+                if !allowed.iter().any(|a| a == value) {
+                    violation(Reason::NotAllowed {
+                        value: value.to_string(),
+                        allowed: allowed.clone(),
+                    });
+                }
+            }
+
+            if let Some((min, max)) = self.range {
+                match value.parse::<f64>() {
+                    Ok(number) if number >= min && number <= max => {}
+                    Ok(_) => violation(Reason::OutOfRange {
+                        value: value.to_string(),
+                        min,
+                        max,
+                    }),
+                    Err(_) => violation(Reason::NotANumber {
+                        value: value.to_string(),
+                    }),
+                }
+            }
+
+            #[cfg(feature = "regex")]
+            if let Some(pattern) = &self.pattern {
+                if !pattern.is_match(value) {
+                    violation(Reason::PatternMismatch {
+                        value: value.to_string(),
+                        pattern: pattern.as_str().to_string(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Declarative rules for the keys of a [`QueryMap`]
+#[derive(Clone, Debug, Default)]
+pub struct QuerySchema {
+    params: Vec<(String, Param)>,
+    unknown_keys: UnknownKeys,
+}
+
+impl QuerySchema {
+    /// Create a schema without params, that warns about unknown keys
+    #[must_use]
+    pub fn new() -> Self {
+        QuerySchema::default()
+    }
+
+    /// Declare the rules for a key
+    #[must_use]
+    pub fn param(mut self, key: impl Into<String>, param: Param) -> Self {
+        self.params.push((key.into(), param));
+        self
+    }
+
+    /// Set how keys that are not declared are reported
+    #[must_use]
+    pub fn unknown_keys(mut self, unknown_keys: UnknownKeys) -> Self {
+        self.unknown_keys = unknown_keys;
+        self
+    }
+
+    /// Validate a [`QueryMap`], reporting every violation.
+    ///
+    /// Returns the warnings when there are no errors.
+    pub fn validate(&self, map: &QueryMap) -> Result<Vec<Violation>, ValidationError> {
+        let mut errors = Vec::new();
+        for (key, param) in &self.params {
+            param.check(key, map.all(key), &mut errors);
+        }
+
+        let known = self
+            .params
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect::<HashSet<_>>();
+        let mut unknown = map
+            .keys()
+            .filter(|key| !known.contains(key))
+            .map(|key| Violation {
+                key: key.to_string(),
+                reason: Reason::Unknown,
+            })
+            .collect::<Vec<_>>();
+        unknown.sort_unstable_by(|a, b| a.key.cmp(&b.key));
+
+        let warnings = match self.unknown_keys {
+            UnknownKeys::Allow => Vec::new(),
+            UnknownKeys::Warn => unknown,
+            UnknownKeys::Deny => {
+                errors.extend(unknown);
+                Vec::new()
+            }
+        };
+
+        if errors.is_empty() {
+            Ok(warnings)
+        } else {
+            Err(ValidationError { errors, warnings })
+        }
+    }
+}
+
+/// Reason why a key doesn't satisfy its rules
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Reason {
+    /// A required key is missing
+    Missing,
+    /// The key has fewer values than required
+    TooFewValues {
+        /// Minimum number of values
+        min: usize,
+        /// Number of values in the map
+        found: usize,
+    },
+    /// The key has more values than allowed
+    TooManyValues {
+        /// Maximum number of values
+        max: usize,
+        /// Number of values in the map
+        found: usize,
+    },
+    /// The value is not one of the allowed values
+    NotAllowed {
+        /// The rejected value
+        value: String,
+        /// The allowed values
+        allowed: Vec<String>,
+    },
+    /// The value is not a number
+    NotANumber {
+        /// The rejected value
+        value: String,
+    },
+    /// The value is outside of the allowed range
+    OutOfRange {
+        /// The rejected value
+        value: String,
+        /// Minimum value
+        min: f64,
+        /// Maximum value
+        max: f64,
+    },
+    /// The value doesn't match the pattern
+    PatternMismatch {
+        /// The rejected value
+        value: String,
+        /// The regular expression
+        pattern: String,
+    },
+    /// The key is not declared in the schema
+    Unknown,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Missing => write!(f, "is required"),
+            Reason::TooFewValues { min, found } => {
+                write!(f, "expects at least {} values, found {}", min, found)
+            }
+            Reason::TooManyValues { max, found } => {
+                write!(f, "expects at most {} values, found {}", max, found)
+            }
+            Reason::NotAllowed { value, allowed } => {
+                write!(f, "value `{}` is not one of: {}", value, allowed.join(", "))
+            }
+            Reason::NotANumber { value } => write!(f, "value `{}` is not a number", value),
+            Reason::OutOfRange { value, min, max } => {
+                write!(f, "value `{}` is not between {} and {}", value, min, max)
+            }
+            Reason::PatternMismatch { value, pattern } => {
+                write!(f, "value `{}` doesn't match `{}`", value, pattern)
+            }
+            Reason::Unknown => write!(f, "is not a known parameter"),
+        }
+    }
+}
+
+/// A key that doesn't satisfy its rules
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// The key name
+    pub key: String,
+    /// Why the key was rejected
+    pub reason: Reason,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` {}", self.key, self.reason)
+    }
+}
+
+/// Error returned when a [`QueryMap`] doesn't satisfy a [`QuerySchema`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    /// Every violation found
+    pub errors: Vec<Violation>,
+    /// Violations reported as warnings
+    pub warnings: Vec<Violation>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid query parameters: ")?;
+        for (i, violation) in self.errors.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationError {}
+
+#[cfg(feature = "actix")]
+impl actix_web::ResponseError for ValidationError {
+    fn status_code(&self) -> actix_web::http::StatusCode {
+        actix_web::http::StatusCode::BAD_REQUEST
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_map as map;
+
+    fn schema() -> QuerySchema {
+        QuerySchema::new()
+            .param("page", Param::new().single().range(1.0, 100.0))
+            .param("sort", Param::new().required().one_of(["asc", "desc"]))
+            .param("tag", Param::new().min_values(2).max_values(3))
+    }
+
+    #[test]
+    fn test_valid_map() {
+        let data = map(&[("page", "2"), ("sort", "asc"), ("tag", "a"), ("tag", "b")]);
+        assert_eq!(Ok(vec![]), schema().validate(&data));
+    }
+
+    #[test]
+    fn test_report_every_violation() {
+        let data = map(&[("page", "0"), ("page", "x"), ("tag", "a")]);
+        let err = schema().validate(&data).unwrap_err();
+
+        let reasons = err
+            .errors
+            .iter()
+            .map(|v| (v.key.as_str(), &v.reason))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("page", &Reason::TooManyValues { max: 1, found: 2 }),
+                (
+                    "page",
+                    &Reason::OutOfRange {
+                        value: "0".into(),
+                        min: 1.0,
+                        max: 100.0
+                    }
+                ),
+                ("page", &Reason::NotANumber { value: "x".into() }),
+                ("sort", &Reason::Missing),
+                ("tag", &Reason::TooFewValues { min: 2, found: 1 }),
+            ],
+            reasons
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let data = map(&[("sort", "asc"), ("b", "1"), ("a", "1")]);
+
+        let warnings = schema().validate(&data).unwrap();
+        assert_eq!(
+            vec!["a", "b"],
+            warnings.iter().map(|v| v.key.as_str()).collect::<Vec<_>>()
+        );
+
+        let allowed = schema().unknown_keys(UnknownKeys::Allow);
+        assert_eq!(Ok(vec![]), allowed.validate(&data));
+
+        let err = schema()
+            .unknown_keys(UnknownKeys::Deny)
+            .validate(&data)
+            .unwrap_err();
+        assert_eq!(
+            "invalid query parameters: `a` is not a known parameter; `b` is not a known parameter",
+            err.to_string()
+        );
+    }
+
+    #[cfg(feature = "actix")]
+    #[test]
+    fn test_response_error() {
+        use actix_web::{http::StatusCode, ResponseError};
+
+        let err = schema().validate(&map(&[("sort", "up")])).unwrap_err();
+        assert_eq!(StatusCode::BAD_REQUEST, err.status_code());
+        assert_eq!(StatusCode::BAD_REQUEST, err.error_response().status());
+    }
+
+    #[test]
+    fn test_display() {
+        let err = schema().validate(&map(&[("sort", "up")])).unwrap_err();
+        assert_eq!(
+            "invalid query parameters: `sort` value `up` is not one of: asc, desc",
+            err.to_string()
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_pattern() {
+        let schema = QuerySchema::new().param(
+            "id",
+            Param::new().pattern(regex::Regex::new("^[0-9a-f]{4}$").unwrap()),
+        );
+        assert!(schema.validate(&map(&[("id", "beef")])).is_ok());
+
+        let err = schema.validate(&map(&[("id", "nope")])).unwrap_err();
+        assert_eq!(
+            Reason::PatternMismatch {
+                value: "nope".into(),
+                pattern: "^[0-9a-f]{4}$".into()
+            },
+            err.errors[0].reason
+        );
+    }
+}